@@ -0,0 +1,85 @@
+// What every chapter's build.rs does with its assets/ folder, shared so it
+// isn't pasted into each of them
+//
+//     fn main() {
+//         build_assets::build();
+//     }
+//
+// The chapter also needs an embedded-assets feature and, in main,
+//
+//     #[cfg(feature = "embedded-assets")]
+//     common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+// Copies assets/ next to the binary and, with the embedded-assets feature,
+// writes the table main includes
+pub fn build() {
+    let target = output_path().join("assets");
+    let _result = copy_dir_all("assets", target);
+
+    if env::var_os("CARGO_FEATURE_EMBEDDED_ASSETS").is_some() {
+        embed_assets().expect("Could not embed the assets");
+    }
+}
+
+fn output_path() -> PathBuf {
+    //<root or manifest path>/target/<profile>/
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let build_type = env::var("PROFILE").unwrap();
+
+    Path::new(&manifest_dir).join("target").join(build_type)
+}
+
+fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
+    fs::create_dir_all(&dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let ty = entry.file_type()?;
+        if ty.is_dir() {
+            copy_dir_all(entry.path(), dst.as_ref().join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), dst.as_ref().join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+// Every file under dir with its path from the package folder
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Writes OUT_DIR/assets.rs, a table of every asset for common::assets::embed
+fn embed_assets() -> io::Result<()> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let mut files = Vec::new();
+    list_files(Path::new("assets"), &mut files)?;
+    // The same table every build whatever order the folder is read in
+    files.sort();
+
+    let mut table = String::from("&[\n");
+    for file in files {
+        let name = file.to_string_lossy().replace('\\', "/");
+        let full_path = manifest_dir.join(&file);
+        table.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            name,
+            full_path.to_string_lossy()
+        ));
+    }
+    table.push(']');
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("assets.rs"), table)
+}
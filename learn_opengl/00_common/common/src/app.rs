@@ -0,0 +1,662 @@
+use glfw::{Action, Context as _, Key, Modifiers, WindowEvent};
+use log::{error, info, warn};
+
+use crate::assets::Assets;
+use crate::context;
+use crate::debug;
+use crate::display::{self, Display, WindowMode};
+use crate::headless;
+use crate::input::Input;
+use crate::options::Options;
+use crate::profile::Profiler;
+use crate::record::Recorder;
+use crate::replay::{self, InputRecorder, InputReplay};
+use crate::scene::CameraStart;
+use crate::screenshot;
+use crate::session::Session;
+use crate::time::Clock;
+
+pub const SCR_WIDTH: u32 = 800;
+pub const SCR_HEIGHT: u32 = 600;
+
+pub(crate) struct Window {
+    pub(crate) glfw: glfw::Glfw,
+    pub(crate) handle: glfw::PWindow,
+}
+
+// Everything a chapter needs from the runner while it is running
+pub struct Context {
+    // None when rendering headless
+    pub(crate) window: Option<Window>,
+    pub time: Clock,
+    pub input: Input,
+    pub profiler: Profiler,
+    pub assets: Assets,
+    pub wireframe: bool,
+    // The framebuffer is what gets rendered to, on high DPI screens it is
+    // bigger than the window, which is in screen coordinates like the mouse
+    width: u32,
+    height: u32,
+    window_size: (u32, u32),
+    content_scale: (f32, f32),
+    minimized: bool,
+    display: Display,
+    // None when the run shouldn't remember anything, headless or replaying
+    session: Option<Session>,
+    closed: bool,
+    screenshot_scale: u32,
+    // Set by F12, taken after the next frame is rendered
+    pending_screenshot: Option<u32>,
+}
+
+impl Context {
+    pub(crate) fn headless(options: &Options) -> Context {
+        let (width, height) = options.size();
+
+        // Same images every run, whatever the loader threads are up to and
+        // whatever is edited while it runs
+        let mut assets = Assets::new();
+        assets.set_wait_for_loads(true);
+        assets.set_hot_reload(false);
+
+        Context {
+            window: None,
+            time: Clock::default(),
+            input: Input::new(),
+            profiler: Profiler::new(options),
+            assets,
+            wireframe: false,
+            width,
+            height,
+            window_size: (width, height),
+            content_scale: (1.0, 1.0),
+            minimized: false,
+            display: Display::new(options, WindowMode::Windowed),
+            session: None,
+            closed: false,
+            screenshot_scale: 1,
+            pending_screenshot: None,
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // 1 while minimized so projections don't divide by 0
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.width == 0 || self.height == 0 {
+            return 1.0;
+        }
+
+        self.width as f32 / self.height as f32
+    }
+
+    pub fn window_size(&self) -> (u32, u32) {
+        self.window_size
+    }
+
+    // How much bigger the OS draws things than at 96 dpi, 2 on most high
+    // DPI screens. Multiply text and UI sizes by it.
+    pub fn content_scale(&self) -> (f32, f32) {
+        self.content_scale
+    }
+
+    // Nothing is updated or drawn while minimized, the framebuffer is 0x0
+    pub fn is_minimized(&self) -> bool {
+        self.minimized || self.width == 0 || self.height == 0
+    }
+
+    // Hides the cursor and keeps it in the window for mouse look
+    pub fn capture_cursor(&mut self) {
+        if let Some(window) = &mut self.window {
+            window.handle.set_cursor_mode(glfw::CursorMode::Disabled);
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        self.display.mode()
+    }
+
+    // Going back to WindowMode::Windowed restores the window's old position
+    // and size. Does nothing headless.
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        if let Some(window) = &mut self.window {
+            self.display.set_mode(window, mode);
+        }
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.display.vsync()
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        if let Some(window) = &mut self.window {
+            self.display.set_vsync(window, vsync);
+        }
+    }
+
+    // Where the camera was when the chapter was last closed, chapters with a
+    // camera start there instead of their scene's start
+    pub fn saved_camera(&self) -> Option<CameraStart> {
+        self.session.as_ref()?.camera.clone()
+    }
+
+    // Call from App::shutdown to have the next run start from here
+    pub fn save_camera(&mut self, camera: CameraStart) {
+        if let Some(session) = &mut self.session {
+            session.camera = Some(camera);
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    pub fn should_close(&self) -> bool {
+        self.closed
+            || self
+                .window
+                .as_ref()
+                .is_some_and(|window| window.handle.should_close())
+    }
+
+    pub fn toggle_wireframe(&mut self) {
+        self.wireframe = !self.wireframe;
+
+        if self.wireframe {
+            unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
+        } else {
+            unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
+        }
+    }
+
+    // Escape, F, F12 and the time controls work the same in every chapter
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent) {
+        self.input.handle_event(event);
+
+        match *event {
+            WindowEvent::FramebufferSize(width, height) => {
+                self.width = width.max(0) as u32;
+                self.height = height.max(0) as u32;
+
+                // A 0x0 viewport is fine but skip it anyway, the old one is
+                // right again when the window comes back
+                if width > 0 && height > 0 {
+                    unsafe { gl::Viewport(0, 0, width, height) };
+                }
+            }
+            WindowEvent::Size(width, height) => {
+                self.window_size = (width.max(0) as u32, height.max(0) as u32);
+            }
+            WindowEvent::ContentScale(x_scale, y_scale) => {
+                self.content_scale = (x_scale, y_scale);
+            }
+            WindowEvent::Iconify(minimized) => {
+                self.minimized = minimized;
+            }
+            WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                self.close();
+            }
+            WindowEvent::Key(Key::F, _, Action::Press, _) => {
+                self.toggle_wireframe();
+            }
+            WindowEvent::Key(Key::P, _, Action::Press, _) => {
+                self.time.toggle_pause();
+                info!(
+                    "{}",
+                    if self.time.is_paused() {
+                        "Paused"
+                    } else {
+                        "Resumed"
+                    }
+                );
+            }
+            WindowEvent::Key(Key::Period, _, Action::Press | Action::Repeat, _) => {
+                self.time.step();
+            }
+            WindowEvent::Key(Key::LeftBracket, _, Action::Press, _) => {
+                self.time.set_scale(self.time.scale() / 2.0);
+                info!("Time scale {}", self.time.scale());
+            }
+            WindowEvent::Key(Key::RightBracket, _, Action::Press, _) => {
+                self.time.set_scale(self.time.scale() * 2.0);
+                info!("Time scale {}", self.time.scale());
+            }
+            WindowEvent::Key(Key::F11, _, Action::Press, modifiers) => {
+                let mode = if modifiers.contains(Modifiers::Shift) {
+                    WindowMode::Exclusive
+                } else {
+                    WindowMode::Borderless
+                };
+
+                if let Some(window) = &mut self.window {
+                    self.display.toggle_mode(window, mode);
+                }
+            }
+            WindowEvent::Key(Key::F10, _, Action::Press, _) => {
+                if let Some(window) = &mut self.window {
+                    self.display.next_monitor(window);
+                }
+            }
+            WindowEvent::Key(Key::F9, _, Action::Press, _) => {
+                if let Some(window) = &mut self.window {
+                    self.display.next_video_mode(window);
+                }
+            }
+            WindowEvent::Key(Key::V, _, Action::Press, _) => {
+                let vsync = !self.vsync();
+                self.set_vsync(vsync);
+            }
+            WindowEvent::Key(Key::F12, _, Action::Press, modifiers) => {
+                if modifiers.contains(Modifiers::Shift) {
+                    self.pending_screenshot = Some(self.screenshot_scale);
+                } else {
+                    self.pending_screenshot = Some(1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// A chapter only implements its own setup, drawing and teardown
+pub trait App: Sized {
+    fn init(ctx: &mut Context) -> Self;
+
+    // Called every ctx.time.fixed_delta of simulated time, zero or more times
+    // a frame before update. Simulation that has to come out the same every
+    // run goes here, render can blend the last two states with ctx.time.alpha.
+    fn fixed_update(&mut self, _ctx: &mut Context) {}
+
+    // Called once a frame before render, input has been updated by then
+    fn update(&mut self, _ctx: &mut Context) {}
+
+    fn render(&mut self, ctx: &mut Context);
+
+    fn event(&mut self, _ctx: &mut Context, _event: &WindowEvent) {}
+
+    // The framebuffer changed size, ctx.width() and ctx.aspect_ratio() are
+    // already updated. Offscreen targets that match the window are resized
+    // here. Not called for a minimized window.
+    fn resize(&mut self, _ctx: &mut Context, _width: u32, _height: u32) {}
+
+    // GL objects should be deleted here while the context is still alive
+    fn shutdown(&mut self, _ctx: &mut Context) {}
+}
+
+// Opens a window for the chapter, or renders it offscreen with --headless
+pub fn run<A: App>(title: &str) {
+    // RUST_LOG=debug shows more, RUST_LOG=warn only shows problems
+    let _ = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .try_init();
+
+    let mut options = Options::from_args();
+
+    // A replay renders at the recorded size for as many frames as were recorded
+    let replay = options.replay.as_ref().map(|path| {
+        let replay = InputReplay::load(path).unwrap_or_else(|error| {
+            error!("{}", error);
+            std::process::exit(1);
+        });
+
+        options.size = options.size.or(Some(replay.size()));
+        options.frames = replay.len().max(1) as u32;
+        replay
+    });
+
+    if options.headless {
+        headless::run::<A>(&options, replay);
+    } else {
+        run_windowed::<A>(title, &options, replay);
+    }
+}
+
+fn run_windowed<A: App>(title: &str, options: &Options, mut replay: Option<InputReplay>) {
+    use glfw::fail_on_errors;
+    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
+
+    let (major, minor) = options.gl_version;
+
+    glfw.window_hint(glfw::WindowHint::Resizable(true));
+    glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+
+    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+        glfw::OpenGlProfileHint::Core,
+    ));
+
+    if options.msaa > 0 {
+        glfw.window_hint(glfw::WindowHint::Samples(Some(options.msaa)));
+    }
+
+    if options.gl_debug {
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(true));
+    }
+
+    // A replay has to start the same as the recording did, so neither of
+    // them restores or saves the last run. Neither does --no-session.
+    let session =
+        (!options.no_session && options.replay.is_none() && options.record_input.is_none())
+            .then(Session::load);
+
+    let (mut window, events, mode) = create_window(&mut glfw, title, options);
+
+    window.make_current();
+    context::make_current();
+    window.set_key_polling(true);
+    window.set_cursor_pos_polling(true);
+    window.set_scroll_polling(true);
+    window.set_focus_polling(true);
+    window.set_framebuffer_size_polling(true);
+    window.set_size_polling(true);
+    window.set_content_scale_polling(true);
+    window.set_iconify_polling(true);
+
+    gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
+    debug::init(options);
+
+    if options.msaa > 0 {
+        unsafe { gl::Enable(gl::MULTISAMPLE) };
+    }
+
+    // The framebuffer is bigger than the window on high DPI screens
+    let (width, height) = window.get_framebuffer_size();
+    let (window_width, window_height) = window.get_size();
+    let content_scale = window.get_content_scale();
+
+    let mut ctx = Context {
+        window: Some(Window {
+            glfw,
+            handle: window,
+        }),
+        time: Clock::default(),
+        input: Input::new(),
+        profiler: Profiler::new(options),
+        assets: Assets::new(),
+        wireframe: false,
+        width: width as u32,
+        height: height as u32,
+        window_size: (window_width as u32, window_height as u32),
+        content_scale,
+        minimized: false,
+        display: Display::new(options, mode),
+        session,
+        closed: false,
+        screenshot_scale: options.screenshot_scale,
+        pending_screenshot: None,
+    };
+
+    display::swap_interval(ctx.window.as_mut().unwrap(), options.vsync);
+
+    // Size and fullscreen given on the command line win over the last run
+    let saved_window = ctx.session.as_ref().and_then(|session| session.window);
+
+    if let Some(state) = saved_window.filter(|_| options.size.is_none() && !options.fullscreen) {
+        let window = ctx.window.as_mut().unwrap();
+        ctx.display.restore(window, &state);
+
+        // The resize events only arrive with the first poll, the input
+        // recorder and init want the restored size now
+        let (width, height) = window.handle.get_framebuffer_size();
+        let (window_width, window_height) = window.handle.get_size();
+        ctx.width = width as u32;
+        ctx.height = height as u32;
+        ctx.window_size = (window_width as u32, window_height as u32);
+    }
+
+    let mut recorder = options
+        .record
+        .as_ref()
+        .map(|path| open_recorder(path, options));
+
+    let mut input_recorder = options
+        .record_input
+        .as_ref()
+        .map(|path| InputRecorder::new(path, (ctx.width, ctx.height)));
+
+    let mut app = A::init(&mut ctx);
+    debug::check_hook("App::init");
+
+    if ctx
+        .session
+        .as_ref()
+        .is_some_and(|session| session.wireframe)
+    {
+        ctx.toggle_wireframe();
+    }
+
+    while !ctx.should_close() {
+        // Checked before the frame is begun, so the profiler isn't left with
+        // one that never ends
+        let replayed = match &mut replay {
+            Some(replay) => match replay.next_frame() {
+                Some(frame) => Some(frame),
+                None => break,
+            },
+            None => None,
+        };
+
+        ctx.profiler.begin_frame();
+        ctx.profiler.begin("events");
+
+        let window = ctx.window.as_mut().unwrap();
+        window.glfw.poll_events();
+
+        let mut frame_events: Vec<WindowEvent> = glfw::flush_messages(&events)
+            .map(|(_, event)| event)
+            .collect();
+
+        // Recording steps time by a fixed amount instead of the real time,
+        // a replay uses the times it was recorded with
+        let mut now = match &recorder {
+            Some(recorder) => (recorder.frames() + 1) as f32 * options.timestep(),
+            None => window.glfw.get_time() as f32,
+        };
+
+        if let Some(frame) = replayed {
+            // The window still moves and resizes itself and Escape still
+            // quits, the rest of the input comes from the recording
+            frame_events.retain(|event| {
+                !replay::is_input(event)
+                    || matches!(event, WindowEvent::Key(Key::Escape, _, Action::Press, _))
+            });
+            frame_events.extend(frame.events.iter().cloned());
+            now = frame.time;
+        }
+
+        let fixed_updates = ctx.time.tick(now);
+
+        for event in &frame_events {
+            ctx.handle_event(event);
+            app.event(&mut ctx, event);
+
+            if let WindowEvent::FramebufferSize(width, height) = *event {
+                if width > 0 && height > 0 {
+                    app.resize(&mut ctx, width as u32, height as u32);
+                }
+            }
+        }
+
+        if let Some(input_recorder) = &mut input_recorder {
+            input_recorder.record(now, &frame_events, &ctx.input);
+        }
+
+        if let Some(replay) = &mut replay {
+            replay.check(&ctx.input);
+        }
+
+        ctx.profiler.end();
+
+        // There is nothing to draw to, so wait for the window to come back
+        // instead of spinning
+        if ctx.is_minimized() {
+            ctx.input.end_frame();
+            ctx.window.as_mut().unwrap().glfw.wait_events_timeout(0.1);
+            continue;
+        }
+
+        // Background loads that finished go to the GPU before anything runs
+        ctx.profiler.begin("uploads");
+        ctx.assets.update();
+        ctx.profiler.end();
+
+        ctx.profiler.begin("fixed_update");
+        for _ in 0..fixed_updates {
+            app.fixed_update(&mut ctx);
+        }
+        debug::check_hook("App::fixed_update");
+        ctx.profiler.end();
+
+        ctx.profiler.begin("update");
+        app.update(&mut ctx);
+        debug::check_hook("App::update");
+        ctx.profiler.end();
+
+        ctx.profiler.begin("render");
+        app.render(&mut ctx);
+        debug::check_hook("App::render");
+        ctx.profiler.end();
+
+        if let Some(scale) = ctx.pending_screenshot.take() {
+            take_screenshot(&mut app, &mut ctx, scale);
+        }
+
+        if let Some(recorder) = &mut recorder {
+            let (width, height) = ctx.window.as_ref().unwrap().handle.get_framebuffer_size();
+            let image = screenshot::capture_window(width as u32, height as u32);
+
+            if let Err(error) = recorder.record(image) {
+                error!("Stopped recording: {}", error);
+                ctx.close();
+            }
+
+            if recorder.frames() >= options.frames {
+                ctx.close();
+            }
+        }
+
+        debug::check_frame();
+
+        ctx.input.end_frame();
+
+        ctx.profiler.begin("swap");
+
+        if let Some(window) = &mut ctx.window {
+            window.handle.swap_buffers();
+        }
+
+        ctx.profiler.end();
+    }
+
+    app.shutdown(&mut ctx);
+    debug::check_hook("App::shutdown");
+    ctx.profiler.finish();
+
+    // Buffers and vertex arrays the chapter still owns are deleted while
+    // the context is alive
+    drop(app);
+
+    if let Some(mut session) = ctx.session.take() {
+        session.window = Some(ctx.display.state(ctx.window.as_ref().unwrap()));
+        session.wireframe = ctx.wireframe;
+        session.save();
+    }
+
+    if let Some(recorder) = recorder {
+        recorder.finish();
+    }
+
+    if let Some(input_recorder) = input_recorder {
+        if let Err(error) = input_recorder.finish() {
+            error!("{}", error);
+        }
+    }
+
+    // The window and its context go away with ctx
+    context::release();
+}
+
+// Fullscreen windows default to the monitor's current resolution so the
+// video mode doesn't have to change. Also returns the mode it ended up with,
+// a missing monitor means a window even with --fullscreen.
+fn create_window(
+    glfw: &mut glfw::Glfw,
+    title: &str,
+    options: &Options,
+) -> (
+    glfw::PWindow,
+    glfw::GlfwReceiver<(f64, WindowEvent)>,
+    WindowMode,
+) {
+    let window = glfw.with_connected_monitors(|glfw, monitors| {
+        let monitor = options
+            .fullscreen
+            .then(|| monitors.get(options.monitor))
+            .flatten();
+
+        if options.fullscreen && monitor.is_none() {
+            warn!(
+                "There is no monitor {}, opening a window instead",
+                options.monitor
+            );
+        }
+
+        match monitor {
+            Some(monitor) => {
+                let (width, height) = options.size.unwrap_or_else(|| {
+                    monitor
+                        .get_video_mode()
+                        .map_or((SCR_WIDTH, SCR_HEIGHT), |mode| (mode.width, mode.height))
+                });
+
+                glfw.create_window(width, height, title, glfw::WindowMode::FullScreen(monitor))
+                    .map(|(window, events)| (window, events, WindowMode::Exclusive))
+            }
+            None => {
+                let (width, height) = options.size();
+
+                glfw.create_window(width, height, title, glfw::WindowMode::Windowed)
+                    .map(|(window, events)| (window, events, WindowMode::Windowed))
+            }
+        }
+    });
+
+    window.unwrap_or_else(|| {
+        let (major, minor) = options.gl_version;
+        error!(
+            "Could not create a window with an OpenGL {}.{} context",
+            major, minor
+        );
+        std::process::exit(1);
+    })
+}
+
+pub(crate) fn open_recorder(path: &std::path::Path, options: &Options) -> Recorder {
+    Recorder::new(path, options.fps).unwrap_or_else(|error| {
+        error!("Could not start recording: {}", error);
+        std::process::exit(1);
+    })
+}
+
+fn take_screenshot<A: App>(app: &mut A, ctx: &mut Context, scale: u32) {
+    // The framebuffer can be bigger than the window on high DPI screens
+    let (width, height) = ctx.window.as_ref().unwrap().handle.get_framebuffer_size();
+    let (width, height) = (width as u32, height as u32);
+
+    let image = if scale > 1 {
+        screenshot::capture_supersampled(app, ctx, width, height, scale)
+    } else {
+        screenshot::capture_window(width, height)
+    };
+
+    screenshot::save(&image);
+}
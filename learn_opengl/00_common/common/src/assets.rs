@@ -0,0 +1,589 @@
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use image::DynamicImage;
+use log::{debug, log, warn, Level};
+
+use crate::loader::Loader;
+use crate::scene::modified_time;
+use crate::texture::{Texture2D, TextureOptions};
+
+// Points at the folder a chapter's assets/ folder is in, for running a
+// binary from anywhere
+pub const ASSETS_ENV: &str = "LEARNOPENGL_ASSETS";
+
+// Files packed into the binary by the embedded-assets feature, paths like
+// "assets/shaders/color.vs" with their contents
+pub type EmbeddedFiles = &'static [(&'static str, &'static [u8])];
+
+static EMBEDDED: OnceLock<EmbeddedFiles> = OnceLock::new();
+
+// Called from main before common::run with the table build.rs generates
+//
+//     #[cfg(feature = "embedded-assets")]
+//     common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
+pub fn embed(files: EmbeddedFiles) {
+    if EMBEDDED.set(files).is_err() {
+        warn!("Assets were already embedded, ignoring the second set");
+    }
+}
+
+// Anything that can be loaded from one file and shared, like a mesh. The path
+// is for error messages, embedded files don't have one on disk.
+pub trait Asset: Sized + 'static {
+    fn load(path: &Path, data: &[u8]) -> Result<Self, String>;
+}
+
+// How many bytes of textures loaded in the background go to the GPU each
+// frame unless set_upload_budget changes it
+pub const DEFAULT_UPLOAD_BUDGET: usize = 8 * 1024 * 1024;
+
+type Slot<T> = RefCell<Option<Result<Rc<T>, String>>>;
+
+// An asset being loaded in the background
+//
+//     let mesh = ctx.assets.load_async::<Mesh>("assets/models/cube.obj")?;
+//     ...
+//     if let Some(mesh) = mesh.get() { ... }
+pub struct Loading<T> {
+    slot: Rc<Slot<T>>,
+}
+
+impl<T> Loading<T> {
+    // None until it has loaded, and if it failed
+    pub fn get(&self) -> Option<Rc<T>> {
+        match &*self.slot.borrow() {
+            Some(Ok(asset)) => Some(asset.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.slot.borrow().is_some()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        match &*self.slot.borrow() {
+            Some(Err(error)) => Some(error.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<T> Clone for Loading<T> {
+    fn clone(&self) -> Loading<T> {
+        Loading {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+// A texture loaded from a file on disk, loaded again when the file changes
+struct WatchedTexture {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    options: TextureOptions,
+    texture: Weak<Texture2D>,
+}
+
+// Where a file was found
+#[derive(Clone)]
+pub(crate) enum Source {
+    Embedded(&'static str, &'static [u8]),
+    File(PathBuf),
+}
+
+impl Source {
+    // What the caches know it by
+    fn key(&self) -> PathBuf {
+        match self {
+            Source::Embedded(name, _) => PathBuf::from(name),
+            Source::File(path) => path.clone(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Source::Embedded(name, _) => Path::new(name),
+            Source::File(path) => path,
+        }
+    }
+
+    pub(crate) fn read(&self) -> Result<Cow<'static, [u8]>, String> {
+        match self {
+            Source::Embedded(_, data) => Ok(Cow::Borrowed(data)),
+            Source::File(path) => std::fs::read(path)
+                .map(Cow::Owned)
+                .map_err(|error| format!("{}: {}", path.display(), error)),
+        }
+    }
+}
+
+// Finds the files chapters ask for like "assets/textures/container.jpg" and
+// loads each one once. Files embedded in the binary come first, then paths
+// are tried against, in order:
+//
+//   $LEARNOPENGL_ASSETS
+//   the chapter's folder when started with cargo run
+//   the folder the binary is in, where build.rs copies assets/ to
+//   the current folder
+//
+// Loaded things are handed out as Rc handles. The cache only holds on to
+// textures and Asset types weakly, a texture is deleted when the last handle
+// is dropped and loaded again the next time it is asked for. Text is the
+// exception, see below.
+//
+// Textures read from disk are watched, when their file changes it is decoded
+// again in the background and the new image put in the same texture.
+pub struct Assets {
+    embedded: EmbeddedFiles,
+    roots: Vec<PathBuf>,
+    textures: HashMap<PathBuf, Vec<(TextureOptions, Weak<Texture2D>)>>,
+    watched: Vec<WatchedTexture>,
+    hot_reload: bool,
+    // Held strongly for the whole run since shader sources are small. Each
+    // file is read once, so an edited shader isn't picked up until the
+    // chapter is started again.
+    text: HashMap<PathBuf, Rc<str>>,
+    other: HashMap<(TypeId, PathBuf), Weak<dyn Any>>,
+    // The slots behind Loading handles, so asking twice shares the load
+    loading: HashMap<(TypeId, PathBuf), Weak<dyn Any>>,
+    loader: Loader,
+    upload_budget: usize,
+    pixel_buffers: bool,
+    // Headless runs finish every load before the frame that asked for it so
+    // the images come out the same every time
+    wait_for_loads: bool,
+}
+
+impl Assets {
+    pub fn new() -> Assets {
+        let mut roots = Vec::new();
+
+        if let Some(dir) = std::env::var_os(ASSETS_ENV) {
+            roots.push(PathBuf::from(dir));
+        }
+
+        // Set by cargo run and cargo test, the binary doesn't know it otherwise
+        if let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
+            roots.push(PathBuf::from(dir));
+        }
+
+        if let Some(dir) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            roots.push(dir);
+        }
+
+        roots.push(PathBuf::from("."));
+
+        debug!("Looking for assets in {:?}", roots);
+        Assets::with_roots(roots)
+    }
+
+    pub fn with_roots(roots: Vec<PathBuf>) -> Assets {
+        let embedded = EMBEDDED.get().copied().unwrap_or(&[]);
+        if !embedded.is_empty() {
+            debug!("{} assets are embedded", embedded.len());
+        }
+
+        Assets {
+            embedded,
+            roots,
+            textures: HashMap::new(),
+            watched: Vec::new(),
+            hot_reload: true,
+            text: HashMap::new(),
+            other: HashMap::new(),
+            loading: HashMap::new(),
+            loader: Loader::new(),
+            upload_budget: DEFAULT_UPLOAD_BUDGET,
+            pixel_buffers: false,
+            wait_for_loads: false,
+        }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    // The first root the file is on disk in, embedded files aren't looked
+    // at. Absolute paths are used as they are.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf, String> {
+        let path = path.as_ref();
+
+        if path.is_absolute() {
+            return Ok(path.to_path_buf());
+        }
+
+        self.roots
+            .iter()
+            .map(|root| root.join(path))
+            .find(|candidate| candidate.exists())
+            .ok_or_else(|| {
+                let roots: Vec<_> = self
+                    .roots
+                    .iter()
+                    .map(|root| root.display().to_string())
+                    .collect();
+
+                format!(
+                    "Could not find {} in {}, set {} to the folder it is in",
+                    path.display(),
+                    roots.join(", "),
+                    ASSETS_ENV
+                )
+            })
+    }
+
+    pub fn is_embedded<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.embedded_file(path.as_ref()).is_some()
+    }
+
+    // The whole file, from the binary if it is embedded
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Cow<'static, [u8]>, String> {
+        self.find(path.as_ref())?.read()
+    }
+
+    pub(crate) fn find(&self, path: &Path) -> Result<Source, String> {
+        match self.embedded_file(path) {
+            Some((name, data)) => Ok(Source::Embedded(name, data)),
+            None => self.resolve(path).map(Source::File),
+        }
+    }
+
+    // Embedded names always use / and have no leading ./
+    fn embedded_file(&self, path: &Path) -> Option<(&'static str, &'static [u8])> {
+        let name = path.to_string_lossy().replace('\\', "/");
+        let name = name.trim_start_matches("./");
+
+        self.embedded
+            .iter()
+            .find(|(embedded, _)| *embedded == name)
+            .copied()
+    }
+
+    // The same file with different options is a different texture
+    pub fn texture<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &TextureOptions,
+    ) -> Result<Rc<Texture2D>, String> {
+        let source = self.find(path.as_ref())?;
+        let entries = self.textures.entry(source.key()).or_default();
+        entries.retain(|(_, texture)| texture.strong_count() > 0);
+
+        if let Some(texture) = entries
+            .iter()
+            .find(|(loaded, _)| loaded == options)
+            .and_then(|(_, texture)| texture.upgrade())
+        {
+            return Ok(texture);
+        }
+
+        // Read before it is loaded so a change while loading isn't missed
+        let modified = modified_time(source.path());
+        let data = source.read()?;
+        let texture = Texture2D::from_memory(&data, options)
+            .map_err(|error| format!("{}: {}", source.path().display(), error))?;
+
+        if let Some(name) = source.path().file_name() {
+            texture.label(&name.to_string_lossy());
+        }
+
+        let texture = Rc::new(texture);
+        entries.push((*options, Rc::downgrade(&texture)));
+        self.watch(&source, modified, options, &texture);
+        Ok(texture)
+    }
+
+    // Returns right away with a white placeholder, the file is decoded on a
+    // worker thread and the image put in the same texture a few frames later
+    pub fn texture_async<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &TextureOptions,
+    ) -> Result<Rc<Texture2D>, String> {
+        let source = self.find(path.as_ref())?;
+        let entries = self.textures.entry(source.key()).or_default();
+        entries.retain(|(_, texture)| texture.strong_count() > 0);
+
+        if let Some(texture) = entries
+            .iter()
+            .find(|(loaded, _)| loaded == options)
+            .and_then(|(_, texture)| texture.upgrade())
+        {
+            return Ok(texture);
+        }
+
+        let texture = Rc::new(Texture2D::placeholder(options));
+        if let Some(name) = source.path().file_name() {
+            texture.label(&name.to_string_lossy());
+        }
+        entries.push((*options, Rc::downgrade(&texture)));
+
+        self.watch(&source, modified_time(source.path()), options, &texture);
+        self.decode_texture(source, &texture, *options, false);
+        Ok(texture)
+    }
+
+    // Decodes the file on a worker thread and puts the image in the texture
+    // when it is finished, if anything still holds on to the texture
+    fn decode_texture(
+        &mut self,
+        source: Source,
+        texture: &Rc<Texture2D>,
+        options: TextureOptions,
+        reload: bool,
+    ) {
+        let name = source.path().display().to_string();
+        let weak = Rc::downgrade(texture);
+        let pixel_buffers = self.pixel_buffers;
+
+        self.loader.submit(
+            Box::new(move || {
+                let data = source.read()?;
+                let image = image::load_from_memory(&data)
+                    .map_err(|error| format!("{}: {}", source.path().display(), error))?;
+                let image = if options.flip { image.flipv() } else { image };
+
+                Ok(Box::new(image))
+            }),
+            Box::new(move |decoded| {
+                // Nobody wants it anymore
+                let Some(texture) = weak.upgrade() else {
+                    return 0;
+                };
+
+                match decoded {
+                    Ok(image) => {
+                        let image = image.downcast::<DynamicImage>().unwrap();
+                        let level = if reload { Level::Info } else { Level::Debug };
+                        log!(
+                            level,
+                            "{} {} {}x{} {:?}",
+                            if reload { "Reloaded" } else { "Loaded" },
+                            name,
+                            image.width(),
+                            image.height(),
+                            image.color()
+                        );
+                        texture.finish_loading(&image, &options, pixel_buffers)
+                    }
+                    // A file that is still being written usually fails, it
+                    // is tried again when the write changes it once more
+                    Err(error) if reload => {
+                        warn!("{}, keeping the last image", error);
+                        0
+                    }
+                    Err(error) => {
+                        warn!("{}, keeping the placeholder", error);
+                        texture.failed_loading();
+                        0
+                    }
+                }
+            }),
+        );
+    }
+
+    // Embedded textures can't change
+    fn watch(
+        &mut self,
+        source: &Source,
+        modified: Option<SystemTime>,
+        options: &TextureOptions,
+        texture: &Rc<Texture2D>,
+    ) {
+        if let Source::File(path) = source {
+            self.watched.push(WatchedTexture {
+                path: path.clone(),
+                modified,
+                options: *options,
+                texture: Rc::downgrade(texture),
+            });
+        }
+    }
+
+    // Like SceneFile, polled once a frame. Anything that is still loaded
+    // gets the new image a few frames later.
+    fn reload_changed_textures(&mut self) {
+        self.watched
+            .retain(|watched| watched.texture.strong_count() > 0);
+
+        let mut changed = Vec::new();
+
+        for watched in &mut self.watched {
+            let modified = modified_time(&watched.path);
+
+            if modified.is_none() || modified == watched.modified {
+                continue;
+            }
+
+            watched.modified = modified;
+
+            if let Some(texture) = watched.texture.upgrade() {
+                changed.push((watched.path.clone(), watched.options, texture));
+            }
+        }
+
+        for (path, options, texture) in changed {
+            debug!("{} changed, reloading it", path.display());
+            self.decode_texture(Source::File(path), &texture, options, true);
+        }
+    }
+
+    // A text file like a shader source
+    pub fn text<P: AsRef<Path>>(&mut self, path: P) -> Result<Rc<str>, String> {
+        let source = self.find(path.as_ref())?;
+        let key = source.key();
+
+        if let Some(text) = self.text.get(&key) {
+            return Ok(text.clone());
+        }
+
+        let text: Rc<str> = String::from_utf8(source.read()?.into_owned())
+            .map_err(|error| format!("{}: {}", source.path().display(), error))?
+            .into();
+
+        self.text.insert(key, text.clone());
+        Ok(text)
+    }
+
+    pub fn load<T: Asset, P: AsRef<Path>>(&mut self, path: P) -> Result<Rc<T>, String> {
+        let source = self.find(path.as_ref())?;
+        let key = (TypeId::of::<T>(), source.key());
+
+        if let Some(asset) = self
+            .other
+            .get(&key)
+            .and_then(Weak::upgrade)
+            .and_then(|asset| asset.downcast::<T>().ok())
+        {
+            return Ok(asset);
+        }
+
+        let asset = Rc::new(T::load(source.path(), &source.read()?)?);
+        let weak: Weak<dyn Any> = Rc::downgrade(&(asset.clone() as Rc<dyn Any>));
+        self.other.insert(key, weak);
+        Ok(asset)
+    }
+
+    // Asset::load runs on a worker thread so T has to be Send. It's shared
+    // while any Loading handle or the loaded Rc is around.
+    pub fn load_async<T: Asset + Send, P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Loading<T>, String> {
+        let source = self.find(path.as_ref())?;
+        let key = (TypeId::of::<T>(), source.key());
+
+        if let Some(slot) = self
+            .loading
+            .get(&key)
+            .and_then(Weak::upgrade)
+            .and_then(|slot| slot.downcast::<Slot<T>>().ok())
+        {
+            return Ok(Loading { slot });
+        }
+
+        // Already loaded by load
+        if let Some(asset) = self
+            .other
+            .get(&key)
+            .and_then(Weak::upgrade)
+            .and_then(|asset| asset.downcast::<T>().ok())
+        {
+            return Ok(Loading {
+                slot: Rc::new(RefCell::new(Some(Ok(asset)))),
+            });
+        }
+
+        let slot: Rc<Slot<T>> = Rc::new(RefCell::new(None));
+        let weak = Rc::downgrade(&slot);
+        self.loading
+            .insert(key, Rc::downgrade(&(slot.clone() as Rc<dyn Any>)));
+
+        self.loader.submit(
+            Box::new(move || {
+                let data = source.read()?;
+                let asset = T::load(source.path(), &data)?;
+                Ok(Box::new(asset))
+            }),
+            Box::new(move |decoded| {
+                if let Some(slot) = weak.upgrade() {
+                    let asset = decoded.map(|asset| Rc::new(*asset.downcast::<T>().unwrap()));
+                    if let Err(error) = &asset {
+                        warn!("{}", error);
+                    }
+                    *slot.borrow_mut() = Some(asset);
+                }
+                0
+            }),
+        );
+
+        Ok(Loading { slot })
+    }
+
+    // Background loads that haven't been finished
+    pub fn pending_loads(&self) -> usize {
+        self.loader.len()
+    }
+
+    // Bytes of textures uploaded a frame, at least one texture goes up every
+    // frame however big it is
+    pub fn set_upload_budget(&mut self, bytes: usize) {
+        self.upload_budget = bytes;
+    }
+
+    // Streams background uploads through a pixel buffer object
+    pub fn set_pixel_buffers(&mut self, enabled: bool) {
+        self.pixel_buffers = enabled;
+    }
+
+    // Blocks until everything loading in the background is done, like a
+    // loading screen would
+    pub fn finish_loading(&mut self) {
+        self.loader.update(usize::MAX, true);
+    }
+
+    // Textures are reloaded when their files change unless this turns it off
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+    }
+
+    // Called by the runner at the start of every frame
+    pub(crate) fn update(&mut self) {
+        if self.hot_reload {
+            self.reload_changed_textures();
+        }
+
+        self.loader.update(self.upload_budget, self.wait_for_loads);
+        self.loading.retain(|_, slot| slot.strong_count() > 0);
+    }
+
+    pub(crate) fn set_wait_for_loads(&mut self, wait: bool) {
+        self.wait_for_loads = wait;
+    }
+
+    // Forgets what is loaded, handles that are still around keep working
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.watched.clear();
+        self.text.clear();
+        self.other.clear();
+        self.loading.clear();
+    }
+}
+
+impl Default for Assets {
+    fn default() -> Assets {
+        Assets::new()
+    }
+}
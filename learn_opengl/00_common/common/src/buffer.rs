@@ -0,0 +1,251 @@
+use std::marker::PhantomData;
+use std::mem::{size_of, size_of_val};
+
+use gl::types::{GLenum, GLintptr, GLsizeiptr, GLuint};
+use log::warn;
+use nalgebra_glm as glm;
+
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+
+// How often the data is expected to change, a hint for where the driver
+// keeps it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Usage {
+    // Uploaded once and drawn many times
+    Static,
+    // Changed now and then, drawn many times
+    Dynamic,
+    // Changed about every time it is drawn
+    Stream,
+}
+
+impl Usage {
+    fn gl_enum(self) -> GLenum {
+        match self {
+            Usage::Static => gl::STATIC_DRAW,
+            Usage::Dynamic => gl::DYNAMIC_DRAW,
+            Usage::Stream => gl::STREAM_DRAW,
+        }
+    }
+}
+
+/// Plain data the GPU can read byte for byte, what a Buffer holds.
+///
+/// # Safety
+///
+/// Every byte of the type has to be initialized, so no padding and no
+/// pointers, and it has to be a primitive or #[repr(C)]. #[derive(Vertex)]
+/// implements it for vertex structs after checking that.
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for f32 {}
+unsafe impl Pod for f64 {}
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+// glm vectors and matrices are stored as an array of columns
+unsafe impl<T: Pod + glm::Scalar, const R: usize, const C: usize> Pod for glm::TMat<T, R, C> {}
+
+// A GL buffer holding a slice of T, deleted when dropped.
+//
+//     let vbo = Buffer::vertices(&vertices, Usage::Static);
+//     vbo.update(0, &vertices[..3]);
+pub struct Buffer<T: Pod> {
+    id: GLuint,
+    target: GLenum,
+    usage: Usage,
+    len: usize,
+    context: ContextId,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Buffer<T> {
+    // target is the binding point it is used through, usually
+    // gl::ARRAY_BUFFER or gl::ELEMENT_ARRAY_BUFFER. The buffer is left bound
+    // to the target it was uploaded through, see upload_target.
+    pub fn new(target: GLenum, data: &[T], usage: Usage) -> Buffer<T> {
+        let context = context::expect_current("buffer");
+        let mut id = 0;
+
+        unsafe { gl::GenBuffers(1, &mut id) };
+
+        let mut buffer = Buffer {
+            id,
+            target,
+            usage,
+            len: 0,
+            context,
+            _marker: PhantomData,
+        };
+        buffer.set_data(data);
+        buffer
+    }
+
+    pub fn vertices(data: &[T], usage: Usage) -> Buffer<T> {
+        Buffer::new(gl::ARRAY_BUFFER, data, usage)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    // Number of T in the buffer
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindBuffer(self.target, self.id) };
+    }
+
+    // Names it for debug messages and frame captures
+    pub fn label(&self, name: &str) {
+        debug::label(gl::BUFFER, self.id, name);
+    }
+
+    // Replaces everything, the buffer grows or shrinks to fit
+    pub fn set_data(&mut self, data: &[T]) {
+        let target = self.upload_target();
+
+        unsafe {
+            gl::BindBuffer(target, self.id);
+            gl_check!(gl::BufferData(
+                target,
+                size_of_val(data) as GLsizeiptr,
+                data.as_ptr().cast(),
+                self.usage.gl_enum(),
+            ));
+        }
+
+        self.len = data.len();
+    }
+
+    // Overwrites part of the buffer starting at element offset, the size
+    // stays the same
+    pub fn update(&self, offset: usize, data: &[T]) {
+        assert!(
+            offset + data.len() <= self.len,
+            "Updating {}..{} of a buffer with {} elements",
+            offset,
+            offset + data.len(),
+            self.len
+        );
+
+        let target = self.upload_target();
+
+        unsafe {
+            gl::BindBuffer(target, self.id);
+            gl_check!(gl::BufferSubData(
+                target,
+                (offset * size_of::<T>()) as GLintptr,
+                size_of_val(data) as GLsizeiptr,
+                data.as_ptr().cast(),
+            ));
+        }
+    }
+
+    // Binding to ELEMENT_ARRAY_BUFFER would swap out the index buffer of
+    // whatever vertex array is bound, element buffers are uploaded through
+    // COPY_WRITE_BUFFER instead, which nothing draws from
+    fn upload_target(&self) -> GLenum {
+        if self.target == gl::ELEMENT_ARRAY_BUFFER {
+            gl::COPY_WRITE_BUFFER
+        } else {
+            self.target
+        }
+    }
+}
+
+impl<T: Pod> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe { gl::DeleteBuffers(1, &self.id) };
+        } else {
+            warn!(
+                "Leaking buffer {}, its OpenGL context is not current",
+                self.id
+            );
+        }
+    }
+}
+
+// The integer types indices can be
+pub trait Index: Pod {
+    const GL_TYPE: GLenum;
+}
+
+impl Index for u8 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_BYTE;
+}
+
+impl Index for u16 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_SHORT;
+}
+
+impl Index for u32 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_INT;
+}
+
+// Indices for glDrawElements. Attach it to a VertexArray, which remembers it
+// and knows how many indices to draw.
+pub struct ElementBuffer<I: Index = u32> {
+    buffer: Buffer<I>,
+}
+
+impl<I: Index> ElementBuffer<I> {
+    // Doesn't touch the bound vertex array, attach it to one with
+    // VertexArray::set_elements
+    pub fn new(indices: &[I], usage: Usage) -> ElementBuffer<I> {
+        ElementBuffer {
+            buffer: Buffer::new(gl::ELEMENT_ARRAY_BUFFER, indices, usage),
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.buffer.id()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn index_type(&self) -> GLenum {
+        I::GL_TYPE
+    }
+
+    pub fn bind(&self) {
+        self.buffer.bind();
+    }
+
+    pub fn label(&self, name: &str) {
+        self.buffer.label(name);
+    }
+
+    pub fn set_data(&mut self, indices: &[I]) {
+        self.buffer.set_data(indices);
+    }
+
+    pub fn update(&self, offset: usize, indices: &[I]) {
+        self.buffer.update(offset, indices);
+    }
+}
@@ -0,0 +1,160 @@
+use nalgebra_glm as glm;
+
+use crate::scene::CameraStart;
+
+// The fly camera from the tutorial's camera.h, for the chapters past the
+// camera lesson that would otherwise each carry a copy of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMovement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+}
+
+pub const YAW: f32 = -90.0;
+pub const PITCH: f32 = 0.0;
+pub const SPEED: f32 = 2.5;
+pub const SENSITIVITY: f32 = 0.1;
+pub const ZOOM: f32 = 45.0;
+
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pub position: glm::Vec3,
+    pub front: glm::Vec3,
+    pub up: glm::Vec3,
+    pub right: glm::Vec3,
+    pub world_up: glm::Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub movement_speed: f32,
+    pub mouse_sensitivity: f32,
+    pub zoom: f32,
+}
+
+impl Camera {
+    pub fn new(position: glm::Vec3, up: glm::Vec3, yaw: f32, pitch: f32) -> Camera {
+        let mut camera = Camera {
+            position,
+            front: glm::vec3(0.0, 0.0, -1.0),
+            up,
+            right: glm::vec3(1.0, 0.0, 0.0),
+            world_up: up,
+            yaw,
+            pitch,
+            movement_speed: SPEED,
+            mouse_sensitivity: SENSITIVITY,
+            zoom: ZOOM,
+        };
+        camera.update_camera_vectors();
+        camera
+    }
+
+    // Where a scene file or the last session put the camera
+    pub fn from_start(start: &CameraStart) -> Camera {
+        let mut camera = Camera::new(
+            start.position.into(),
+            glm::vec3(0.0, 1.0, 0.0),
+            start.yaw,
+            start.pitch,
+        );
+        camera.zoom = start.fov;
+        camera
+    }
+
+    // What to hand ctx.save_camera so the next run starts here
+    pub fn start(&self) -> CameraStart {
+        CameraStart {
+            position: self.position.into(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            fov: self.zoom,
+        }
+    }
+
+    pub fn view_matrix(&self) -> glm::Mat4 {
+        glm::look_at(&self.position, &(self.position + self.front), &self.up)
+    }
+
+    pub fn process_keyboard(&mut self, direction: CameraMovement, delta_time: f32) {
+        let velocity = self.movement_speed * delta_time;
+
+        match direction {
+            CameraMovement::Forward => self.position += self.front * velocity,
+            CameraMovement::Backward => self.position -= self.front * velocity,
+            CameraMovement::Left => self.position -= self.right * velocity,
+            CameraMovement::Right => self.position += self.right * velocity,
+        }
+    }
+
+    pub fn process_mouse_movement(&mut self, x_offset: f32, y_offset: f32, constrain_pitch: bool) {
+        self.yaw += x_offset * self.mouse_sensitivity;
+        self.pitch += y_offset * self.mouse_sensitivity;
+
+        if constrain_pitch {
+            self.pitch = self.pitch.clamp(-89.0, 89.0);
+        }
+
+        self.update_camera_vectors();
+    }
+
+    pub fn process_mouse_scroll(&mut self, y_offset: f32) {
+        self.zoom = (self.zoom - y_offset).clamp(1.0, 45.0);
+    }
+
+    pub fn update_camera_vectors(&mut self) {
+        let front = glm::vec3(
+            self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
+            self.pitch.to_radians().sin(),
+            self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
+        );
+
+        self.front = glm::normalize(&front);
+        self.right = glm::normalize(&glm::cross(&self.front, &self.world_up));
+        self.up = glm::normalize(&glm::cross(&self.right, &self.front));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_where_it_was_saved() {
+        let start = CameraStart {
+            position: [1.0, 2.0, 3.0],
+            yaw: 10.0,
+            pitch: -20.0,
+            fov: 30.0,
+        };
+
+        let saved = Camera::from_start(&start).start();
+        assert_eq!(saved.position, start.position);
+        assert_eq!(saved.yaw, start.yaw);
+        assert_eq!(saved.pitch, start.pitch);
+        assert_eq!(saved.fov, start.fov);
+    }
+
+    #[test]
+    fn pitch_and_zoom_stay_in_range() {
+        let mut camera = Camera::from_start(&CameraStart::default());
+
+        camera.process_mouse_movement(0.0, 10_000.0, true);
+        assert_eq!(camera.pitch, 89.0);
+
+        camera.process_mouse_scroll(100.0);
+        assert_eq!(camera.zoom, 1.0);
+        camera.process_mouse_scroll(-100.0);
+        assert_eq!(camera.zoom, 45.0);
+    }
+
+    #[test]
+    fn forward_follows_the_yaw() {
+        let mut camera = Camera::from_start(&CameraStart::default());
+        camera.process_keyboard(CameraMovement::Forward, 1.0);
+
+        // The default camera looks down -z
+        assert!((camera.position.z - (3.0 - SPEED)).abs() < 1e-5);
+        assert!(camera.position.x.abs() < 1e-5);
+    }
+}
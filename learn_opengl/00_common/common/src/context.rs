@@ -0,0 +1,37 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Which GL context is current on this thread as far as the runner knows.
+// GL objects remember the context they were made in so dropping one after
+// the context is gone, or on another thread, leaks it instead of calling
+// into a driver that has nothing current.
+thread_local! {
+    static CURRENT: Cell<Option<ContextId>> = const { Cell::new(None) };
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextId(u64);
+
+// Called by the runners right after making a context current
+pub(crate) fn make_current() -> ContextId {
+    let id = ContextId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    CURRENT.with(|current| current.set(Some(id)));
+    id
+}
+
+// Called before the context is destroyed
+pub(crate) fn release() {
+    CURRENT.with(|current| current.set(None));
+}
+
+pub fn current() -> Option<ContextId> {
+    CURRENT.with(Cell::get)
+}
+
+// Creating GL objects without a context fails in confusing ways or crashes,
+// so stop with a clear message instead
+pub(crate) fn expect_current(what: &str) -> ContextId {
+    current().unwrap_or_else(|| panic!("Creating a {} needs a current OpenGL context", what))
+}
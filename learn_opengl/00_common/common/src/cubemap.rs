@@ -0,0 +1,371 @@
+use std::path::Path;
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use image::DynamicImage;
+use log::{debug, warn};
+
+use crate::assets::Assets;
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+use crate::program::Program;
+use crate::texture::{self, Sampler, Texture2D, TextureOptions, Upload, Wrap};
+use crate::vertex_array::VertexArray;
+
+// The order faces are given in, the same as the tutorial's list of files and
+// GL_TEXTURE_CUBE_MAP_POSITIVE_X + i
+pub const FACES: [&str; 6] = ["right", "left", "top", "bottom", "front", "back"];
+
+// Six square textures sampled with a direction instead of a coordinate,
+// for skyboxes and reflections.
+//
+//     let sky = Cubemap::from_assets(
+//         &ctx.assets,
+//         FACES.map(|face| format!("assets/textures/skybox/{}.jpg", face)),
+//         &Default::default(),
+//     )
+//     .unwrap();
+//
+// Faces aren't flipped like 2D textures usually are, cube maps start at the
+// top row. Wrapping is always clamp to edge, anything else shows seams
+// where the faces meet.
+pub struct Cubemap {
+    id: GLuint,
+    size: u32,
+    mipmaps: bool,
+    context: ContextId,
+}
+
+impl Cubemap {
+    pub fn from_files<P: AsRef<Path>>(
+        faces: [P; 6],
+        options: &TextureOptions,
+    ) -> Result<Cubemap, String> {
+        let images = decode_faces(faces, |path| {
+            std::fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))
+        })?;
+
+        Cubemap::from_images(&images, options)
+    }
+
+    // Found like any other asset, embedded faces included
+    pub fn from_assets<P: AsRef<Path>>(
+        assets: &Assets,
+        faces: [P; 6],
+        options: &TextureOptions,
+    ) -> Result<Cubemap, String> {
+        let images = decode_faces(faces, |path| {
+            assets.read(path).map(|data| data.into_owned())
+        })?;
+
+        Cubemap::from_images(&images, options)
+    }
+
+    // Every face has to be square, the same size and decode to the same
+    // format, the swizzle for grey images is set once for the whole cube map.
+    // The texture is left bound to the active unit.
+    pub fn from_images(
+        faces: &[DynamicImage; 6],
+        options: &TextureOptions,
+    ) -> Result<Cubemap, String> {
+        check_faces(faces)?;
+        let size = faces[0].width();
+
+        let mut cubemap = Cubemap::create(size);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap.id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        }
+
+        let mut swizzle = None;
+
+        for (index, face) in faces.iter().enumerate() {
+            let face = if options.flip {
+                face.flipv()
+            } else {
+                face.clone()
+            };
+            let upload = Upload::new(&face, options.srgb);
+            // The same for every face, check_faces made sure they agree
+            swizzle = upload.swizzle;
+
+            unsafe {
+                gl_check!(gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + index as GLenum,
+                    0,
+                    upload.internal_format as GLint,
+                    size as GLsizei,
+                    size as GLsizei,
+                    0,
+                    upload.format,
+                    upload.component_type,
+                    upload.data.as_ptr().cast(),
+                ));
+            }
+        }
+
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            let swizzle = swizzle
+                .unwrap_or([gl::RED, gl::GREEN, gl::BLUE, gl::ALPHA])
+                .map(|channel| channel as GLint);
+            gl::TexParameteriv(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_SWIZZLE_RGBA,
+                swizzle.as_ptr(),
+            );
+        }
+
+        cubemap.set_sampler(&options.sampler);
+        debug!("Loaded a {}x{} cube map", size, size);
+        Ok(cubemap)
+    }
+
+    // Renders a panorama like the ones HDR environment maps come as into the
+    // six faces, size pixels square each. Load the panorama with flip on so
+    // up is up. Faces are stored as 16 bit floats so an HDR panorama keeps
+    // its range.
+    pub fn from_equirectangular(
+        panorama: &Texture2D,
+        size: u32,
+        sampler: &Sampler,
+    ) -> Result<Cubemap, String> {
+        let program = Program::new(
+            "equirectangular_to_cubemap",
+            include_str!("shaders/equirectangular.vs"),
+            include_str!("shaders/equirectangular.fs"),
+        )?;
+
+        let mut cubemap = Cubemap::create(size);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap.id);
+
+            for index in 0..6 {
+                gl_check!(gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + index,
+                    0,
+                    gl::RGB16F as GLint,
+                    size as GLsizei,
+                    size as GLsizei,
+                    0,
+                    gl::RGB,
+                    gl::FLOAT,
+                    std::ptr::null(),
+                ));
+            }
+        }
+
+        // Rendering into the faces changes the framebuffer and viewport the
+        // chapter or the headless runner has set up, put them back after
+        let mut framebuffer = 0;
+        let mut viewport = [0; 4];
+
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+
+            let mut capture = 0;
+            gl::GenFramebuffers(1, &mut capture);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, capture);
+            gl::Viewport(0, 0, size as GLsizei, size as GLsizei);
+
+            // The vertex shader makes up its own triangle, but core profile
+            // won't draw without a vertex array bound
+            let empty = VertexArray::new();
+            program.use_program();
+            program.set_int("equirectangularMap", 0);
+            panorama.bind(0);
+
+            let mut result = Ok(());
+
+            for index in 0..6 {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + index,
+                    cubemap.id,
+                    0,
+                );
+
+                let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+                if status != gl::FRAMEBUFFER_COMPLETE {
+                    result = Err(format!(
+                        "Could not render into the cube map, framebuffer status = {:#x}",
+                        status
+                    ));
+                    break;
+                }
+
+                program.set_int("face", index as i32);
+                empty.draw_arrays(gl::TRIANGLES, 0, 3);
+            }
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+            gl::DeleteFramebuffers(1, &capture);
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+            VertexArray::unbind();
+
+            result?;
+        }
+
+        cubemap.set_sampler(sampler);
+        debug!(
+            "Converted a {}x{} panorama to a {}x{} cube map",
+            panorama.width(),
+            panorama.height(),
+            size,
+            size
+        );
+        Ok(cubemap)
+    }
+
+    fn create(size: u32) -> Cubemap {
+        let context = context::expect_current("cube map");
+        let mut id = 0;
+
+        unsafe { gl::GenTextures(1, &mut id) };
+
+        Cubemap {
+            id,
+            size,
+            mipmaps: false,
+            context,
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    // Width and height of every face
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+        }
+    }
+
+    pub fn label(&self, name: &str) {
+        debug::label(gl::TEXTURE, self.id, name);
+    }
+
+    // The sampler's wrapping is ignored. Leaves the cube map bound to the
+    // active unit.
+    pub fn set_sampler(&mut self, sampler: &Sampler) {
+        let sampler = sampler.wrap(Wrap::ClampToEdge);
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+            texture::set_parameters(gl::TEXTURE_CUBE_MAP, &sampler);
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_WRAP_R,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+
+            if sampler.mipmaps.is_some() && !self.mipmaps {
+                gl_check!(gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP));
+                self.mipmaps = true;
+            }
+        }
+    }
+}
+
+impl Drop for Cubemap {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe { gl::DeleteTextures(1, &self.id) };
+        } else {
+            warn!(
+                "Leaking cube map {}, its OpenGL context is not current",
+                self.id
+            );
+        }
+    }
+}
+
+// Checked before anything is created so a bad set of faces doesn't need a
+// context to be reported
+fn check_faces(faces: &[DynamicImage; 6]) -> Result<(), String> {
+    let size = faces[0].width();
+    let color = faces[0].color();
+
+    for (name, face) in FACES.iter().zip(faces) {
+        if face.width() != size || face.height() != size {
+            return Err(format!(
+                "The {} face is {}x{}, every face has to be {}x{}",
+                name,
+                face.width(),
+                face.height(),
+                size,
+                size
+            ));
+        }
+
+        if face.color() != color {
+            return Err(format!(
+                "The {} face is {:?}, every face has to be {:?} like the {} face",
+                name,
+                face.color(),
+                color,
+                FACES[0]
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_faces<P, F>(faces: [P; 6], mut read: F) -> Result<[DynamicImage; 6], String>
+where
+    P: AsRef<Path>,
+    F: FnMut(&Path) -> Result<Vec<u8>, String>,
+{
+    let mut images = Vec::with_capacity(6);
+
+    for face in &faces {
+        let path = face.as_ref();
+        let data = read(path)?;
+        let image = image::load_from_memory(&data)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        images.push(image);
+    }
+
+    Ok(images.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn faces(last: DynamicImage) -> [DynamicImage; 6] {
+        let face = DynamicImage::new_rgb8(4, 4);
+        [
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face,
+            last,
+        ]
+    }
+
+    #[test]
+    fn faces_have_to_match() {
+        assert!(check_faces(&faces(DynamicImage::new_rgb8(4, 4))).is_ok());
+
+        let error = check_faces(&faces(DynamicImage::new_rgb8(8, 8))).unwrap_err();
+        assert!(error.contains("back face is 8x8"), "{}", error);
+
+        // A grey face would need a different swizzle from the others
+        let error = check_faces(&faces(DynamicImage::new_luma8(4, 4))).unwrap_err();
+        assert!(error.contains("back face is L8"), "{}", error);
+    }
+}
@@ -0,0 +1,251 @@
+use std::backtrace::Backtrace;
+use std::ffi::{c_void, CStr, CString};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
+use log::{debug, error, info, log, warn, Level};
+
+use crate::options::Options;
+
+// How GL errors are found, picked once the context exists
+const PER_FRAME: u8 = 0;
+const PER_CALL: u8 = 1;
+const CALLBACK: u8 = 2;
+
+static MODE: AtomicU8 = AtomicU8::new(PER_FRAME);
+static BREAK_ON_ERROR: AtomicBool = AtomicBool::new(false);
+static HAS_KHR_DEBUG: AtomicBool = AtomicBool::new(false);
+
+// Checks glGetError right after a call when the context has no KHR_debug to
+// report errors as they happen. Only does anything in debug builds with
+// --gl-debug, otherwise it is just the call. Wrap the calls that can fail,
+// uploads, draws and uniforms, binds and gets aren't worth it.
+#[macro_export]
+macro_rules! gl_check {
+    ($call:expr) => {{
+        let result = $call;
+        $crate::debug::check_call(stringify!($call), file!(), line!());
+        result
+    }};
+}
+
+// Sets up error reporting for the current context. With --gl-debug the
+// driver calls back with every message through KHR_debug, without it there
+// is a glGetError check once a frame.
+pub(crate) fn init(options: &Options) {
+    log_context();
+
+    BREAK_ON_ERROR.store(options.break_on_gl_error, Ordering::Relaxed);
+    HAS_KHR_DEBUG.store(has_khr_debug(), Ordering::Relaxed);
+
+    if !options.gl_debug {
+        MODE.store(PER_FRAME, Ordering::Relaxed);
+        return;
+    }
+
+    if HAS_KHR_DEBUG.load(Ordering::Relaxed) {
+        unsafe {
+            gl::Enable(gl::DEBUG_OUTPUT);
+            // Messages arrive on the thread making the call so the backtrace
+            // points at it
+            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            gl::DebugMessageCallback(Some(message_callback), std::ptr::null());
+        }
+
+        MODE.store(CALLBACK, Ordering::Relaxed);
+    } else if cfg!(debug_assertions) {
+        warn!("KHR_debug is not available, checking glGetError after every call");
+        MODE.store(PER_CALL, Ordering::Relaxed);
+    } else {
+        warn!("KHR_debug is not available, checking glGetError once a frame");
+        MODE.store(PER_FRAME, Ordering::Relaxed);
+    }
+}
+
+// Called by gl_check!
+pub fn check_call(call: &str, file: &str, line: u32) {
+    if cfg!(debug_assertions) && MODE.load(Ordering::Relaxed) == PER_CALL {
+        check_errors(&format!("{} at {}:{}", call, file, line));
+    }
+}
+
+// The runner checks after every App hook when it can't check every call,
+// which at least narrows it down to init, update, render or shutdown
+pub(crate) fn check_hook(hook: &str) {
+    if MODE.load(Ordering::Relaxed) == PER_CALL {
+        check_errors(hook);
+    }
+}
+
+pub(crate) fn check_frame() {
+    if MODE.load(Ordering::Relaxed) == PER_FRAME {
+        check_errors("the last frame");
+    }
+}
+
+// Reports every error glGetError has queued up
+pub fn check_errors(location: &str) {
+    loop {
+        let error = unsafe { gl::GetError() };
+
+        if error == gl::NO_ERROR {
+            break;
+        }
+
+        error!("GL error {} in {}", error_name(error), location);
+
+        if BREAK_ON_ERROR.load(Ordering::Relaxed) {
+            panic!("GL error {} in {}", error_name(error), location);
+        }
+    }
+}
+
+// Names an object so debug messages and frame captures show "cube_vao"
+// instead of a number. Does nothing without KHR_debug.
+pub fn label(identifier: GLenum, name: GLuint, label: &str) {
+    if !HAS_KHR_DEBUG.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if let Ok(label) = CString::new(label) {
+        unsafe { gl::ObjectLabel(identifier, name, -1, label.as_ptr()) };
+    }
+}
+
+pub fn error_name(error: GLenum) -> &'static str {
+    match error {
+        gl::INVALID_ENUM => "INVALID_ENUM",
+        gl::INVALID_VALUE => "INVALID_VALUE",
+        gl::INVALID_OPERATION => "INVALID_OPERATION",
+        gl::STACK_OVERFLOW => "STACK_OVERFLOW",
+        gl::STACK_UNDERFLOW => "STACK_UNDERFLOW",
+        gl::OUT_OF_MEMORY => "OUT_OF_MEMORY",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "INVALID_FRAMEBUFFER_OPERATION",
+        gl::CONTEXT_LOST => "CONTEXT_LOST",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn source_name(source: GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "API",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        gl::DEBUG_SOURCE_OTHER => "other",
+        _ => "unknown",
+    }
+}
+
+pub fn type_name(message_type: GLenum) -> &'static str {
+    match message_type {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        gl::DEBUG_TYPE_PUSH_GROUP => "push group",
+        gl::DEBUG_TYPE_POP_GROUP => "pop group",
+        gl::DEBUG_TYPE_OTHER => "other",
+        _ => "unknown",
+    }
+}
+
+pub fn severity_name(severity: GLenum) -> &'static str {
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => "high",
+        gl::DEBUG_SEVERITY_MEDIUM => "medium",
+        gl::DEBUG_SEVERITY_LOW => "low",
+        gl::DEBUG_SEVERITY_NOTIFICATION => "notification",
+        _ => "unknown",
+    }
+}
+
+fn log_context() {
+    let string = |name| unsafe {
+        let value = gl::GetString(name);
+
+        if value.is_null() {
+            "unknown".into()
+        } else {
+            CStr::from_ptr(value.cast()).to_string_lossy()
+        }
+    };
+
+    info!(
+        "OpenGL {} on {} ({})",
+        string(gl::VERSION),
+        string(gl::RENDERER),
+        string(gl::VENDOR)
+    );
+    debug!("GLSL {}", string(gl::SHADING_LANGUAGE_VERSION));
+}
+
+// Core since 4.3, most 3.3 drivers have the extension
+fn has_khr_debug() -> bool {
+    if !gl::DebugMessageCallback::is_loaded() {
+        return false;
+    }
+
+    let mut major: GLint = 0;
+    let mut minor: GLint = 0;
+
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+
+    (major, minor) >= (4, 3) || has_extension("GL_KHR_debug")
+}
+
+// Whether the current context lists the extension, like "GL_KHR_debug"
+pub(crate) fn has_extension(extension: &str) -> bool {
+    let mut count: GLint = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count) };
+
+    (0..count as GLuint).any(|index| {
+        let name = unsafe { gl::GetStringi(gl::EXTENSIONS, index) };
+
+        !name.is_null() && unsafe { CStr::from_ptr(name.cast()) }.to_bytes() == extension.as_bytes()
+    })
+}
+
+extern "system" fn message_callback(
+    source: GLenum,
+    message_type: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    _length: GLsizei,
+    message: *const GLchar,
+    _user_param: *mut c_void,
+) {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+
+    // Notifications are mostly drivers saying where buffers live so they
+    // only show up with RUST_LOG=debug
+    let level = match severity {
+        gl::DEBUG_SEVERITY_HIGH => Level::Error,
+        gl::DEBUG_SEVERITY_MEDIUM => Level::Warn,
+        gl::DEBUG_SEVERITY_LOW => Level::Info,
+        _ => Level::Debug,
+    };
+
+    log!(
+        level,
+        "GL {} from {} ({}, {}): {}",
+        type_name(message_type),
+        source_name(source),
+        severity_name(severity),
+        id,
+        message
+    );
+
+    // Panicking can't unwind out of the driver, so print where the call came
+    // from and stop right here. A debugger stops on the abort too.
+    if message_type == gl::DEBUG_TYPE_ERROR && BREAK_ON_ERROR.load(Ordering::Relaxed) {
+        error!("{}", Backtrace::force_capture());
+        std::process::abort();
+    }
+}
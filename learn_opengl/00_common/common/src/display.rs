@@ -0,0 +1,253 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Window, SCR_HEIGHT, SCR_WIDTH};
+use crate::options::Options;
+use crate::session::WindowState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+    Windowed,
+    // A window without decorations that covers the monitor. Switching to and
+    // from it is instant and other windows can go on top of it.
+    Borderless,
+    // Takes the monitor over and can change its resolution and refresh rate
+    Exclusive,
+}
+
+// Which monitor and video mode the window is on and how it swaps. The runner
+// switches between them with F9, F10, F11 and V.
+pub(crate) struct Display {
+    mode: WindowMode,
+    monitor: usize,
+    // Index into the monitor's video modes for exclusive fullscreen, None is
+    // whatever mode the monitor is in
+    video_mode: Option<usize>,
+    vsync: bool,
+    // Where the window was before it went fullscreen, put back when it returns
+    windowed: Option<(i32, i32, u32, u32)>,
+    size: (u32, u32),
+}
+
+impl Display {
+    // mode is what create_window actually opened, which is a window when
+    // the fullscreen monitor doesn't exist
+    pub(crate) fn new(options: &Options, mode: WindowMode) -> Display {
+        Display {
+            mode,
+            monitor: options.monitor,
+            video_mode: None,
+            vsync: options.vsync,
+            windowed: None,
+            size: options.size.unwrap_or((SCR_WIDTH, SCR_HEIGHT)),
+        }
+    }
+
+    pub(crate) fn mode(&self) -> WindowMode {
+        self.mode
+    }
+
+    pub(crate) fn vsync(&self) -> bool {
+        self.vsync
+    }
+
+    pub(crate) fn set_mode(&mut self, window: &mut Window, mode: WindowMode) {
+        if mode == self.mode {
+            return;
+        }
+
+        if self.mode == WindowMode::Windowed {
+            let (x, y) = window.handle.get_pos();
+            let (width, height) = window.handle.get_size();
+            self.windowed = Some((x, y, width as u32, height as u32));
+        }
+
+        self.mode = mode;
+        self.apply(window);
+    }
+
+    // Pressing the key for the mode it is already in goes back to a window
+    pub(crate) fn toggle_mode(&mut self, window: &mut Window, mode: WindowMode) {
+        if self.mode == mode {
+            self.set_mode(window, WindowMode::Windowed);
+        } else {
+            self.set_mode(window, mode);
+        }
+    }
+
+    // A window moves over when it next goes fullscreen
+    pub(crate) fn next_monitor(&mut self, window: &mut Window) {
+        let count = window
+            .glfw
+            .with_connected_monitors(|_, monitors| monitors.len());
+
+        self.monitor = (self.monitor + 1) % count.max(1);
+        self.video_mode = None;
+
+        if self.mode == WindowMode::Windowed {
+            info!("Monitor {} is used for fullscreen", self.monitor);
+        } else {
+            self.apply(window);
+        }
+    }
+
+    // Only exclusive fullscreen can change the monitor's video mode
+    pub(crate) fn next_video_mode(&mut self, window: &mut Window) {
+        if self.mode != WindowMode::Exclusive {
+            info!("Video modes can only be changed in exclusive fullscreen, press Shift+F11");
+            return;
+        }
+
+        let monitor = self.monitor;
+        let count = window.glfw.with_connected_monitors(|_, monitors| {
+            monitors
+                .get(monitor)
+                .map_or(0, |monitor| monitor.get_video_modes().len())
+        });
+
+        if count == 0 {
+            return;
+        }
+
+        // The list goes from the smallest to the biggest mode, so start at the
+        // biggest and step down
+        self.video_mode = Some(
+            self.video_mode
+                .map_or(count - 1, |index| (index + count - 1) % count),
+        );
+        self.apply(window);
+    }
+
+    // Puts the window back where the last run left it
+    pub(crate) fn restore(&mut self, window: &mut Window, state: &WindowState) {
+        let (x, y) = state.position;
+        let (width, height) = state.size;
+
+        window.handle.set_pos(x, y);
+        window.handle.set_size(width as i32, height as i32);
+
+        self.monitor = state.monitor;
+        self.set_mode(window, state.mode);
+    }
+
+    pub(crate) fn state(&self, window: &Window) -> WindowState {
+        let (x, y, width, height) = match (self.mode, self.windowed) {
+            (WindowMode::Windowed, _) | (_, None) => {
+                let (x, y) = window.handle.get_pos();
+                let (width, height) = window.handle.get_size();
+                (x, y, width as u32, height as u32)
+            }
+            (_, Some(windowed)) => windowed,
+        };
+
+        WindowState {
+            mode: self.mode,
+            monitor: self.monitor,
+            position: (x, y),
+            size: (width, height),
+        }
+    }
+
+    pub(crate) fn set_vsync(&mut self, window: &mut Window, vsync: bool) {
+        self.vsync = vsync;
+        swap_interval(window, vsync);
+        info!("Vsync {}", if vsync { "on" } else { "off" });
+    }
+
+    fn apply(&mut self, window: &mut Window) {
+        let Window { glfw, handle } = window;
+
+        let applied = glfw.with_connected_monitors(|_, monitors| {
+            let Some(monitor) = monitors.get(self.monitor).or(monitors.first()) else {
+                return false;
+            };
+
+            let name = monitor.get_name().unwrap_or_default();
+            let (x, y) = monitor.get_pos();
+            let current = monitor.get_video_mode();
+
+            match self.mode {
+                WindowMode::Windowed => {
+                    // Started fullscreen so there is no old window, center one
+                    let (x, y, width, height) = self.windowed.unwrap_or_else(|| {
+                        let (width, height) = self.size;
+                        let (screen_width, screen_height) =
+                            current.map_or((width, height), |mode| (mode.width, mode.height));
+
+                        (
+                            x + (screen_width as i32 - width as i32) / 2,
+                            y + (screen_height as i32 - height as i32) / 2,
+                            width,
+                            height,
+                        )
+                    });
+
+                    handle.set_decorated(true);
+                    handle.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+                    info!("Windowed {}x{}", width, height);
+                }
+                WindowMode::Borderless => {
+                    let Some(mode) = current else {
+                        return false;
+                    };
+
+                    handle.set_decorated(false);
+                    handle.set_monitor(
+                        glfw::WindowMode::Windowed,
+                        x,
+                        y,
+                        mode.width,
+                        mode.height,
+                        None,
+                    );
+                    info!(
+                        "Borderless fullscreen on {} {}x{}",
+                        name, mode.width, mode.height
+                    );
+                }
+                WindowMode::Exclusive => {
+                    let modes = monitor.get_video_modes();
+                    let Some(mode) = self
+                        .video_mode
+                        .and_then(|index| modes.get(index).copied())
+                        .or(current)
+                    else {
+                        return false;
+                    };
+
+                    handle.set_decorated(true);
+                    handle.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor),
+                        0,
+                        0,
+                        mode.width,
+                        mode.height,
+                        Some(mode.refresh_rate),
+                    );
+                    info!(
+                        "Exclusive fullscreen on {} {}x{} {} Hz",
+                        name, mode.width, mode.height, mode.refresh_rate
+                    );
+                }
+            }
+
+            true
+        });
+
+        if !applied {
+            warn!("There is no monitor to go fullscreen on");
+            self.mode = WindowMode::Windowed;
+        }
+
+        // Some platforms forget the swap interval when the monitor changes
+        swap_interval(window, self.vsync);
+    }
+}
+
+pub(crate) fn swap_interval(window: &mut Window, vsync: bool) {
+    window.glfw.set_swap_interval(if vsync {
+        glfw::SwapInterval::Sync(1)
+    } else {
+        glfw::SwapInterval::None
+    });
+}
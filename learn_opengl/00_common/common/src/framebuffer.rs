@@ -0,0 +1,207 @@
+use gl::types::GLint;
+use image::RgbaImage;
+use log::warn;
+
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+
+// An offscreen render target with a color and depth/stencil attachment that
+// can be read back into an image
+pub struct Framebuffer {
+    fbo: u32,
+    color: u32,
+    depth_stencil: u32,
+    width: u32,
+    height: u32,
+    samples: u32,
+    // Multisampled pixels can't be read directly so they are blitted here first
+    resolve: Option<Box<Framebuffer>>,
+    context: ContextId,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer::with_samples(width, height, 0)
+    }
+
+    // 0 samples is a normal framebuffer, more turns on multisampling
+    pub fn with_samples(width: u32, height: u32, samples: u32) -> Framebuffer {
+        let context = context::expect_current("framebuffer");
+        let mut fbo = 0;
+        let mut color = 0;
+        let mut depth_stencil = 0;
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            debug::label(gl::FRAMEBUFFER, fbo, "offscreen_framebuffer");
+
+            gl::GenRenderbuffers(1, &mut color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, color);
+            debug::label(gl::RENDERBUFFER, color, "offscreen_color");
+            gl_check!(gl::RenderbufferStorageMultisample(
+                gl::RENDERBUFFER,
+                samples as GLint,
+                gl::RGBA8,
+                width as GLint,
+                height as GLint,
+            ));
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                color,
+            );
+
+            gl::GenRenderbuffers(1, &mut depth_stencil);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_stencil);
+            debug::label(gl::RENDERBUFFER, depth_stencil, "offscreen_depth_stencil");
+            gl_check!(gl::RenderbufferStorageMultisample(
+                gl::RENDERBUFFER,
+                samples as GLint,
+                gl::DEPTH24_STENCIL8,
+                width as GLint,
+                height as GLint,
+            ));
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                depth_stencil,
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                panic!("Framebuffer is not complete, status = {:#x}", status);
+            }
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Framebuffer {
+            fbo,
+            color,
+            depth_stencil,
+            width,
+            height,
+            samples,
+            resolve: (samples > 0).then(|| Box::new(Framebuffer::new(width, height))),
+            context,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Recreates the attachments at the new size, whatever was drawn is lost.
+    // Call from App::resize for targets that should match the window. A
+    // minimized window is 0x0, the old size is kept then.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        // The old attachments are deleted when it is dropped
+        *self = Framebuffer::with_samples(width, height, self.samples);
+    }
+
+    // Everything drawn after this ends up in the framebuffer
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width as GLint, self.height as GLint);
+        }
+    }
+
+    pub fn read_pixels(&self) -> RgbaImage {
+        if let Some(resolve) = &self.resolve {
+            let (width, height) = (self.width as GLint, self.height as GLint);
+
+            unsafe {
+                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, resolve.fbo);
+                gl_check!(gl::BlitFramebuffer(
+                    0,
+                    0,
+                    width,
+                    height,
+                    0,
+                    0,
+                    width,
+                    height,
+                    gl::COLOR_BUFFER_BIT,
+                    gl::NEAREST,
+                ));
+                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            }
+
+            return resolve.read_pixels();
+        }
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+        }
+
+        let image = read_pixels(self.width, self.height);
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+
+        image
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe {
+                gl::DeleteFramebuffers(1, &self.fbo);
+                gl::DeleteRenderbuffers(1, &self.color);
+                gl::DeleteRenderbuffers(1, &self.depth_stencil);
+            }
+        } else {
+            warn!(
+                "Leaking framebuffer {}, its OpenGL context is not current",
+                self.fbo
+            );
+        }
+    }
+}
+
+// Reads the bound read framebuffer, GL starts at the bottom left so the
+// rows are flipped to get a normal top to bottom image. Alpha is made opaque
+// because a window shows the colors as they are, a shader writing less than
+// 1 (like mixing in a texture with transparent parts) would otherwise come
+// out faded in the saved image.
+pub fn read_pixels(width: u32, height: u32) -> RgbaImage {
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl_check!(gl::ReadPixels(
+            0,
+            0,
+            width as GLint,
+            height as GLint,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast(),
+        ));
+    }
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = u8::MAX;
+    }
+
+    let image = RgbaImage::from_raw(width, height, pixels).unwrap();
+
+    image::imageops::flip_vertical(&image)
+}
@@ -0,0 +1,225 @@
+use khronos_egl as egl;
+use log::{error, info};
+
+use crate::app::{self, App, Context};
+use crate::context;
+use crate::debug;
+use crate::framebuffer::Framebuffer;
+use crate::options::Options;
+use crate::replay::InputReplay;
+
+// From EGL_MESA_platform_surfaceless, not in the khronos-egl constants
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+// A GL context without any window or surface. With Mesa this runs
+// on llvmpipe so it works on CI machines without a display or GPU.
+pub struct HeadlessContext {
+    egl: egl::DynamicInstance<egl::EGL1_5>,
+    display: egl::Display,
+    context: egl::Context,
+}
+
+impl HeadlessContext {
+    pub fn new(options: &Options) -> Result<HeadlessContext, String> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }
+            .map_err(|error| format!("Could not load libEGL: {}", error))?;
+
+        // Surfaceless needs no display server, the default display is the
+        // fallback for drivers that don't have it
+        let display = unsafe {
+            egl.get_platform_display(
+                PLATFORM_SURFACELESS_MESA,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+        }
+        .ok()
+        .or_else(|| unsafe { egl.get_display(egl::DEFAULT_DISPLAY) })
+        .ok_or("No EGL display available")?;
+
+        egl.initialize(display)
+            .map_err(|error| format!("Could not initialize EGL: {}", error))?;
+
+        // There is no surface so don't ask for the default WINDOW_BIT
+        let config = egl
+            .choose_first_config(
+                display,
+                &[
+                    egl::SURFACE_TYPE,
+                    0,
+                    egl::RENDERABLE_TYPE,
+                    egl::OPENGL_BIT,
+                    egl::NONE,
+                ],
+            )
+            .map_err(|error| format!("Could not choose an EGL config: {}", error))?
+            .ok_or("No EGL config supports desktop OpenGL")?;
+
+        egl.bind_api(egl::OPENGL_API)
+            .map_err(|error| format!("Could not bind the OpenGL API: {}", error))?;
+
+        let (major, minor) = options.gl_version;
+        let mut attributes = vec![
+            egl::CONTEXT_MAJOR_VERSION,
+            major as egl::Int,
+            egl::CONTEXT_MINOR_VERSION,
+            minor as egl::Int,
+            egl::CONTEXT_OPENGL_PROFILE_MASK,
+            egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE,
+            egl::TRUE as egl::Int,
+        ];
+
+        if options.gl_debug {
+            attributes.extend_from_slice(&[egl::CONTEXT_OPENGL_DEBUG, egl::TRUE as egl::Int]);
+        }
+
+        attributes.push(egl::NONE);
+
+        let context = egl
+            .create_context(display, config, None, &attributes)
+            .map_err(|error| {
+                format!(
+                    "Could not create a GL {}.{} context: {}",
+                    major, minor, error
+                )
+            })?;
+
+        egl.make_current(display, None, None, Some(context))
+            .map_err(|error| format!("Could not make the context current: {}", error))?;
+
+        gl::load_with(|name| {
+            egl.get_proc_address(name)
+                .map_or(std::ptr::null(), |function| function as *const _)
+        });
+
+        context::make_current();
+
+        Ok(HeadlessContext {
+            egl,
+            display,
+            context,
+        })
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        context::release();
+        let _ = self.egl.make_current(self.display, None, None, None);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
+
+// Renders the chapter into an offscreen framebuffer with a fixed timestep
+// and saves the last frame
+pub fn run<A: App>(options: &Options, mut replay: Option<InputReplay>) {
+    let _headless = HeadlessContext::new(options).unwrap_or_else(|error| {
+        error!("Could not create a headless OpenGL context: {}", error);
+        std::process::exit(1);
+    });
+    debug::init(options);
+
+    let (width, height) = options.size();
+    let framebuffer = Framebuffer::with_samples(width, height, options.msaa);
+    framebuffer.bind();
+
+    let mut recorder = options
+        .record
+        .as_ref()
+        .map(|path| app::open_recorder(path, options));
+
+    let mut ctx = Context::headless(options);
+    let mut app = A::init(&mut ctx);
+    debug::check_hook("App::init");
+
+    for frame in 1..=options.frames {
+        // There is no window to read input from, but a replay can supply it
+        let mut now = frame as f32 * options.timestep();
+        let mut events = Vec::new();
+
+        if let Some(replay) = &mut replay {
+            // Before the frame is begun, so the profiler isn't left with one
+            // that never ends
+            let Some(recorded) = replay.next_frame() else {
+                break;
+            };
+
+            now = recorded.time;
+            events = recorded.events.clone();
+        }
+
+        ctx.profiler.begin_frame();
+
+        let fixed_updates = ctx.time.tick(now);
+
+        for event in &events {
+            ctx.handle_event(event);
+            app.event(&mut ctx, event);
+        }
+
+        if let Some(replay) = &mut replay {
+            replay.check(&ctx.input);
+        }
+
+        framebuffer.bind();
+
+        // Background loads that finished go to the GPU before anything runs
+        ctx.profiler.begin("uploads");
+        ctx.assets.update();
+        ctx.profiler.end();
+
+        ctx.profiler.begin("fixed_update");
+        for _ in 0..fixed_updates {
+            app.fixed_update(&mut ctx);
+        }
+        debug::check_hook("App::fixed_update");
+        ctx.profiler.end();
+
+        ctx.profiler.begin("update");
+        app.update(&mut ctx);
+        debug::check_hook("App::update");
+        ctx.profiler.end();
+
+        ctx.profiler.begin("render");
+        app.render(&mut ctx);
+        debug::check_hook("App::render");
+        ctx.profiler.end();
+
+        if let Some(recorder) = &mut recorder {
+            if let Err(error) = recorder.record(framebuffer.read_pixels()) {
+                error!("Stopped recording: {}", error);
+                ctx.close();
+            }
+        }
+
+        debug::check_frame();
+
+        ctx.input.end_frame();
+
+        if ctx.should_close() {
+            break;
+        }
+    }
+
+    app.shutdown(&mut ctx);
+    debug::check_hook("App::shutdown");
+    ctx.profiler.finish();
+
+    // The recording already has every frame so there's no need for the last one
+    if let Some(recorder) = recorder {
+        recorder.finish();
+        return;
+    }
+
+    let image = framebuffer.read_pixels();
+
+    match image.save(&options.output) {
+        Ok(()) => info!("Saved {}", options.output.display()),
+        Err(error) => {
+            error!("Could not save {}: {}", options.output.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
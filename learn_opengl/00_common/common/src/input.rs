@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use glfw::{Action, Key, WindowEvent};
+
+// Keyboard and mouse state built up from the window events so chapters can
+// poll it every frame like processInput does in the C++ version
+#[derive(Debug, Default)]
+pub struct Input {
+    keys: HashSet<Key>,
+    first_mouse: bool,
+    last_mouse_x: f32,
+    last_mouse_y: f32,
+    mouse_offset: (f32, f32),
+    scroll_offset: f32,
+}
+
+impl Input {
+    pub fn new() -> Input {
+        Input {
+            first_mouse: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn key_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    // Every key held down, in the same order every time
+    pub fn keys_down(&self) -> Vec<Key> {
+        let mut keys: Vec<Key> = self.keys.iter().copied().collect();
+        keys.sort_by_key(|key| *key as i32);
+        keys
+    }
+
+    // How far the mouse moved since the last frame, y goes from bottom to top
+    pub fn mouse_offset(&self) -> (f32, f32) {
+        self.mouse_offset
+    }
+
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroll_offset
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, _scancode, Action::Press, _mods) => {
+                self.keys.insert(key);
+            }
+            WindowEvent::Key(key, _scancode, Action::Release, _mods) => {
+                self.keys.remove(&key);
+            }
+            WindowEvent::CursorPos(x_position, y_position) => {
+                let x_position = x_position as f32;
+                let y_position = y_position as f32;
+
+                if self.first_mouse {
+                    self.last_mouse_x = x_position;
+                    self.last_mouse_y = y_position;
+                    self.first_mouse = false;
+                }
+
+                self.mouse_offset.0 += x_position - self.last_mouse_x;
+                self.mouse_offset.1 += self.last_mouse_y - y_position;
+
+                self.last_mouse_x = x_position;
+                self.last_mouse_y = y_position;
+            }
+            WindowEvent::Scroll(_x_offset, y_offset) => {
+                self.scroll_offset += y_offset as f32;
+            }
+            WindowEvent::Focus(false) => {
+                // Key releases are lost when the window is not focused
+                self.keys.clear();
+                self.first_mouse = true;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.mouse_offset = (0.0, 0.0);
+        self.scroll_offset = 0.0;
+    }
+}
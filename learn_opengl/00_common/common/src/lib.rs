@@ -0,0 +1,50 @@
+// Shared plumbing for the chapters so each main.rs only has to worry about
+// the rendering code from the lesson it is porting.
+
+pub mod app;
+pub mod assets;
+pub mod buffer;
+pub mod camera;
+pub mod context;
+pub mod cubemap;
+pub mod debug;
+pub mod display;
+pub mod framebuffer;
+pub mod headless;
+pub mod input;
+mod loader;
+pub mod options;
+pub mod profile;
+pub mod program;
+pub mod record;
+pub mod replay;
+pub mod scene;
+pub mod screenshot;
+pub mod session;
+pub mod skybox;
+pub mod texture;
+pub mod time;
+mod trace;
+pub mod vertex;
+pub mod vertex_array;
+
+pub use app::{run, App, Context};
+pub use assets::{Asset, Assets, Loading};
+pub use buffer::{Buffer, ElementBuffer, Pod, Usage};
+pub use camera::{Camera, CameraMovement};
+pub use cubemap::Cubemap;
+pub use debug::label;
+pub use display::WindowMode;
+pub use framebuffer::Framebuffer;
+pub use input::Input;
+pub use options::Options;
+pub use profile::Profiler;
+pub use program::Program;
+pub use scene::{Scene, SceneFile};
+pub use skybox::Skybox;
+pub use texture::{Filter, Sampler, Texture2D, TextureOptions, Wrap};
+pub use time::Clock;
+pub use vertex::Vertex;
+pub use vertex_array::VertexArray;
+// The derive macro, it shares the name with the trait like serde does
+pub use vertex_derive::Vertex;
@@ -0,0 +1,238 @@
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use log::{debug, warn};
+
+// What a worker hands back, decoded data for the GL thread to finish
+pub(crate) type Decoded = Result<Box<dyn Any + Send>, String>;
+
+// Runs on the GL thread with what the worker decoded and returns how many
+// bytes it uploaded, counted against the frame's budget
+pub(crate) type Finish = Box<dyn FnOnce(Decoded) -> usize>;
+
+type Job = Box<dyn FnOnce() -> Decoded + Send>;
+type Jobs = Sender<(u64, Job)>;
+type Results = Receiver<(u64, Decoded)>;
+
+const STOPPED: &str = "The asset loader threads have stopped";
+
+// Decodes on worker threads and finishes on the GL thread. The workers are
+// started the first time something is loaded in the background.
+pub(crate) struct Loader {
+    jobs: Option<Jobs>,
+    // Only the workers can send on it, so it disconnects once they have all
+    // stopped and nothing else is coming
+    results: Option<Results>,
+    worker_count: usize,
+    next_id: u64,
+    // Waiting on a worker
+    pending: HashMap<u64, Finish>,
+    // Decoded but over the last frame's upload budget
+    ready: VecDeque<(Finish, Decoded)>,
+}
+
+impl Loader {
+    pub(crate) fn new() -> Loader {
+        let worker_count = thread::available_parallelism()
+            .map_or(2, |count| count.get())
+            .clamp(1, 4);
+
+        Loader {
+            jobs: None,
+            results: None,
+            worker_count,
+            next_id: 0,
+            pending: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    // How many loads haven't been finished yet
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len() + self.ready.len()
+    }
+
+    pub(crate) fn submit(&mut self, job: Job, finish: Finish) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, finish);
+
+        if self.jobs.is_none() {
+            let (jobs, results) = start_workers(self.worker_count);
+            self.jobs = Some(jobs);
+            self.results = Some(results);
+        }
+
+        let jobs = self.jobs.as_ref().unwrap();
+        if jobs.send((id, job)).is_err() {
+            self.receive(id, Err(STOPPED.to_string()));
+        }
+    }
+
+    // Finishes decoded loads until budget bytes have been uploaded, at least
+    // one a frame so a big image can't hold up the rest forever. wait
+    // finishes everything that was submitted, blocking until it is decoded.
+    pub(crate) fn update(&mut self, budget: usize, wait: bool) {
+        self.collect(wait);
+
+        let mut uploaded = 0;
+        let mut finished = 0;
+
+        while let Some((finish, decoded)) = self.ready.pop_front() {
+            uploaded += finish(decoded);
+            finished += 1;
+
+            if !wait && uploaded >= budget {
+                break;
+            }
+        }
+
+        if finished > 0 {
+            debug!(
+                "Finished {} loads, {} bytes uploaded, {} to go",
+                finished,
+                uploaded,
+                self.len()
+            );
+        }
+    }
+
+    // Moves what the workers have decoded to ready, blocking until nothing
+    // is pending if wait is set. If the workers have all stopped, the loads
+    // still pending fail instead of waiting forever.
+    fn collect(&mut self, wait: bool) {
+        let Some(results) = self.results.take() else {
+            return;
+        };
+
+        loop {
+            let next = if wait && !self.pending.is_empty() {
+                results.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                results.try_recv()
+            };
+
+            match next {
+                Ok((id, decoded)) => self.receive(id, decoded),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.pending.is_empty() {
+                        warn!(
+                            "The asset loader threads have stopped with {} loads left",
+                            self.pending.len()
+                        );
+                    }
+
+                    for (_, finish) in self.pending.drain() {
+                        self.ready.push_back((finish, Err(STOPPED.to_string())));
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.results = Some(results);
+    }
+
+    fn receive(&mut self, id: u64, decoded: Decoded) {
+        if let Some(finish) = self.pending.remove(&id) {
+            self.ready.push_back((finish, decoded));
+        }
+    }
+}
+
+// The workers share the job queue and stop when the loader is dropped.
+// Returns where to send jobs and where their results come back.
+fn start_workers(count: usize) -> (Jobs, Results) {
+    let (jobs, queue) = mpsc::channel::<(u64, Job)>();
+    let queue = Arc::new(Mutex::new(queue));
+    let (results, received) = mpsc::channel();
+
+    for index in 0..count {
+        let queue = queue.clone();
+        let results = results.clone();
+
+        let spawned = thread::Builder::new()
+            .name(format!("asset loader {}", index))
+            .spawn(move || loop {
+                let next = queue.lock().unwrap().recv();
+                let Ok((id, job)) = next else {
+                    break;
+                };
+
+                // A decoder that panics fails its load instead of leaving
+                // it waiting forever
+                let decoded = panic::catch_unwind(AssertUnwindSafe(job))
+                    .unwrap_or_else(|_| Err("The loader panicked".to_string()));
+
+                if results.send((id, decoded)).is_err() {
+                    break;
+                }
+            });
+
+        if let Err(error) = spawned {
+            warn!("Could not start an asset loader thread: {}", error);
+        }
+    }
+
+    debug!("Started {} asset loader threads", count);
+    (jobs, received)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    // Submits a load of value and records what its finish was handed
+    fn submit(loader: &mut Loader, value: u32, finished: &Rc<RefCell<Vec<Result<u32, String>>>>) {
+        let finished = finished.clone();
+        loader.submit(
+            Box::new(move || Ok(Box::new(value) as Box<dyn Any + Send>)),
+            Box::new(move |decoded| {
+                let value = decoded.map(|value| *value.downcast::<u32>().unwrap());
+                finished.borrow_mut().push(value);
+                0
+            }),
+        );
+    }
+
+    #[test]
+    fn waiting_finishes_every_load() {
+        let mut loader = Loader::new();
+        let finished = Rc::new(RefCell::new(Vec::new()));
+
+        for value in 0..8 {
+            submit(&mut loader, value, &finished);
+        }
+        loader.update(0, true);
+
+        let mut values: Vec<u32> = finished
+            .borrow()
+            .iter()
+            .map(|value| *value.as_ref().unwrap())
+            .collect();
+        values.sort();
+        assert_eq!(values, (0..8).collect::<Vec<_>>());
+        assert_eq!(loader.len(), 0);
+    }
+
+    #[test]
+    fn loads_fail_without_workers_instead_of_waiting() {
+        let mut loader = Loader::new();
+        loader.worker_count = 0;
+        let finished = Rc::new(RefCell::new(Vec::new()));
+
+        submit(&mut loader, 1, &finished);
+        loader.update(0, true);
+
+        assert_eq!(*finished.borrow(), vec![Err(STOPPED.to_string())]);
+        assert_eq!(loader.len(), 0);
+    }
+}
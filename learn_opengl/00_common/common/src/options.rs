@@ -0,0 +1,353 @@
+use std::path::PathBuf;
+
+use crate::app::{SCR_HEIGHT, SCR_WIDTH};
+
+const USAGE: &str = "\
+Options:
+    --size <w>x<h>      size of the window or headless framebuffer (default 800x600,
+                        or the monitor's resolution when fullscreen)
+    --fullscreen        open the window fullscreen
+    --monitor <index>   monitor to go fullscreen on, 0 is the primary (default 0)
+    --vsync <on|off>    wait for the monitor refresh when swapping (default on)
+    --msaa <samples>    multisample anti-aliasing samples, 0 turns it off (default 0)
+    --gl <major>.<minor>
+                        OpenGL version of the context, 3.3 or newer (default 3.3)
+    --gl-debug          create a debug context and print the driver's messages
+    --break-on-gl-error stop at the first GL error and print where it happened
+    --profile           time the frame and its parts on the CPU and GPU and print the
+                        statistics every second
+    --trace <path>      save every profiled scope to a Chrome trace-event JSON file when
+                        the chapter exits, for chrome://tracing or ui.perfetto.dev
+    --headless          render offscreen without opening a window
+    --frames <count>    number of frames to render when headless or recording (default 60)
+    --fps <rate>        simulated frames per second when headless or recording (default 60)
+    --output <path>     where the last headless frame is saved (default <binary>.png)
+    --record <path>     record the frames to an animated GIF if the path ends in .gif,
+                        otherwise to a folder of numbered PNGs
+    --record-input <path>
+                        save the keyboard and mouse input of the run to a file
+    --replay <path>     play back input saved with --record-input instead of reading
+                        the keyboard and mouse, also works with --headless
+    --no-session        start fresh instead of restoring the window, camera and wireframe
+                        from the last run
+    --screenshot-scale <n>
+                        how many times bigger Shift+F12 screenshots are (default 2)
+    --help              print this message";
+
+// Command line options every chapter understands
+#[derive(Clone, Debug)]
+pub struct Options {
+    // None leaves it to the default or the monitor when fullscreen
+    pub size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+    pub monitor: usize,
+    pub vsync: bool,
+    pub msaa: u32,
+    pub gl_version: (u32, u32),
+    pub gl_debug: bool,
+    pub break_on_gl_error: bool,
+    pub profile: bool,
+    pub trace: Option<PathBuf>,
+    pub headless: bool,
+    pub frames: u32,
+    pub fps: f32,
+    pub output: PathBuf,
+    pub record: Option<PathBuf>,
+    pub record_input: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub no_session: bool,
+    pub screenshot_scale: u32,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            size: None,
+            fullscreen: false,
+            monitor: 0,
+            vsync: true,
+            msaa: 0,
+            gl_version: (3, 3),
+            gl_debug: false,
+            break_on_gl_error: false,
+            profile: false,
+            trace: None,
+            headless: false,
+            frames: 60,
+            fps: 60.0,
+            output: default_output(),
+            record: None,
+            record_input: None,
+            replay: None,
+            no_session: false,
+            screenshot_scale: 2,
+        }
+    }
+}
+
+// Why parse didn't come back with options
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    // --help was given, from_args prints the usage and exits
+    Help,
+    Invalid(String),
+}
+
+impl From<String> for ParseError {
+    fn from(error: String) -> ParseError {
+        ParseError::Invalid(error)
+    }
+}
+
+impl Options {
+    // Prints the usage and exits on --help or if the arguments can't be
+    // understood
+    pub fn from_args() -> Options {
+        match Options::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(ParseError::Help) => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            Err(ParseError::Invalid(error)) => {
+                eprintln!("{}\n\n{}", error, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, ParseError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    options.size = Some(parse_size(&value)?);
+                }
+                "--fullscreen" => options.fullscreen = true,
+                "--monitor" => options.monitor = parse_value(&arg, args.next())?,
+                "--vsync" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    options.vsync = parse_switch(&arg, &value)?;
+                }
+                "--msaa" => options.msaa = parse_value(&arg, args.next())?,
+                "--gl" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    options.gl_version = parse_version(&value)?;
+                }
+                "--gl-debug" => options.gl_debug = true,
+                "--break-on-gl-error" => options.break_on_gl_error = true,
+                "--profile" => options.profile = true,
+                "--trace" => options.trace = Some(parse_value(&arg, args.next())?),
+                "--headless" => options.headless = true,
+                "--frames" => options.frames = parse_value(&arg, args.next())?,
+                "--fps" => options.fps = parse_value(&arg, args.next())?,
+                "--output" => options.output = parse_value(&arg, args.next())?,
+                "--record" => options.record = Some(parse_value(&arg, args.next())?),
+                "--record-input" => options.record_input = Some(parse_value(&arg, args.next())?),
+                "--replay" => options.replay = Some(parse_value(&arg, args.next())?),
+                "--no-session" => options.no_session = true,
+                "--screenshot-scale" => options.screenshot_scale = parse_value(&arg, args.next())?,
+                "--help" | "-h" => return Err(ParseError::Help),
+                _ => return Err(format!("Unknown option {}", arg).into()),
+            }
+        }
+
+        if options.frames == 0 {
+            return Err("--frames has to be at least 1".to_string().into());
+        }
+
+        if options.screenshot_scale == 0 {
+            return Err("--screenshot-scale has to be at least 1".to_string().into());
+        }
+
+        if options.record_input.is_some() && options.replay.is_some() {
+            return Err("--record-input and --replay can't be used together"
+                .to_string()
+                .into());
+        }
+
+        if options.record_input.is_some() && options.headless {
+            return Err("--record-input needs a window to read input from"
+                .to_string()
+                .into());
+        }
+
+        // NaN and inf parse as floats too
+        if !options.fps.is_finite() || options.fps < 1.0 {
+            return Err("--fps has to be a number of at least 1".to_string().into());
+        }
+
+        Ok(options)
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size.unwrap_or((SCR_WIDTH, SCR_HEIGHT))
+    }
+
+    // Seconds between two simulated frames
+    pub fn timestep(&self) -> f32 {
+        1.0 / self.fps
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", name))?;
+
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid value for {}", value, name))
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || {
+        format!(
+            "{} is not a valid size, expected something like 800x600",
+            value
+        )
+    };
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok((width, height))
+}
+
+fn parse_switch(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" | "1" => Ok(true),
+        "off" | "false" | "0" => Ok(false),
+        _ => Err(format!(
+            "{} is not a valid value for {}, use on or off",
+            value, name
+        )),
+    }
+}
+
+fn parse_version(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || {
+        format!(
+            "{} is not a valid OpenGL version, expected something like 3.3",
+            value
+        )
+    };
+    let (major, minor) = value.split_once('.').ok_or_else(invalid)?;
+    let major: u32 = major.parse().map_err(|_| invalid())?;
+    let minor: u32 = minor.parse().map_err(|_| invalid())?;
+
+    if major == 0 {
+        return Err(invalid());
+    }
+
+    // The shaders are #version 330 core, and the wrappers need 3.1 for
+    // GL_COPY_WRITE_BUFFER
+    if (major, minor) < (3, 3) {
+        return Err(format!(
+            "OpenGL {} is too old, 3.3 is the oldest that works",
+            value
+        ));
+    }
+
+    Ok((major, minor))
+}
+
+// hello_window.png for the hello_window binary and so on
+fn default_output() -> PathBuf {
+    let name = std::env::current_exe()
+        .ok()
+        .and_then(|path| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "frame".to_string());
+
+    PathBuf::from(format!("{}.png", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, ParseError> {
+        Options::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn no_arguments_are_the_defaults() {
+        let options = parse("").unwrap();
+
+        assert_eq!(options.size(), (SCR_WIDTH, SCR_HEIGHT));
+        assert!(options.vsync);
+        assert_eq!(options.gl_version, (3, 3));
+        assert_eq!(options.frames, 60);
+        assert_eq!(options.fps, 60.0);
+        assert!(!options.headless);
+    }
+
+    #[test]
+    fn reads_every_kind_of_value() {
+        let options = parse(
+            "--size 400x300 --vsync off --msaa 4 --gl 4.5 --headless --frames 30 --fps 25 \
+             --output frame.png --record frames --no-session",
+        )
+        .unwrap();
+
+        assert_eq!(options.size, Some((400, 300)));
+        assert!(!options.vsync);
+        assert_eq!(options.msaa, 4);
+        assert_eq!(options.gl_version, (4, 5));
+        assert!(options.headless);
+        assert_eq!(options.frames, 30);
+        assert_eq!(options.timestep(), 1.0 / 25.0);
+        assert_eq!(options.output, PathBuf::from("frame.png"));
+        assert_eq!(options.record, Some(PathBuf::from("frames")));
+        assert!(options.no_session);
+    }
+
+    #[test]
+    fn needs_at_least_gl_3_3() {
+        assert_eq!(parse("--gl 3.3").unwrap().gl_version, (3, 3));
+        assert_eq!(parse("--gl 4.0").unwrap().gl_version, (4, 0));
+        assert!(parse("--gl 3.2").is_err());
+        assert!(parse("--gl 2.1").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for args in [
+            "--wat",
+            "--frames",
+            "--frames many",
+            "--frames 0",
+            "--size 800",
+            "--size 0x600",
+            "--vsync maybe",
+            "--gl 0.3",
+            "--fps 0",
+            "--fps 0.3",
+            "--fps NaN",
+            "--fps inf",
+            "--screenshot-scale 0",
+            "--record-input in.ron --replay in.ron",
+            "--record-input in.ron --headless",
+        ] {
+            assert!(
+                matches!(parse(args), Err(ParseError::Invalid(_))),
+                "{} was accepted",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn help_is_not_an_error() {
+        assert_eq!(parse("--help").unwrap_err(), ParseError::Help);
+        assert_eq!(parse("--size 400x300 -h").unwrap_err(), ParseError::Help);
+    }
+}
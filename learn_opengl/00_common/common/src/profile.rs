@@ -0,0 +1,369 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use gl::types::{GLsizei, GLuint, GLuint64};
+use log::info;
+
+use crate::options::Options;
+use crate::trace::Trace;
+
+// How many frames the statistics are taken over
+const WINDOW: usize = 300;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Times named scopes on the CPU and the GPU and keeps rolling statistics of
+// them. Turned on with --profile, or --trace to also save every scope to a
+// trace file, otherwise every call does nothing.
+//
+//     ctx.profiler.begin("draw");
+//     gl::DrawArrays(gl::TRIANGLES, 0, 36);
+//     ctx.profiler.end();
+//
+// Scopes can nest and a scope's time includes its children's.
+pub struct Profiler {
+    enabled: bool,
+    report: bool,
+    frame: usize,
+    frame_start: Option<Instant>,
+    // When the frames whose GPU queries are still in flight started
+    submitted: [Option<Instant>; 2],
+    last_report: Instant,
+    next_id: u64,
+    open: Vec<OpenScope>,
+    trace: Option<Trace>,
+    gpu: GpuTimer,
+    cpu_samples: BTreeMap<&'static str, Samples>,
+    gpu_samples: BTreeMap<&'static str, Samples>,
+}
+
+struct OpenScope {
+    id: u64,
+    name: &'static str,
+    start: Instant,
+}
+
+// GL_TIME_ELAPSED queries can't overlap, so the open query always belongs to
+// the innermost scope. Starting a child ends the parent's query and ending
+// the child starts a new one for the parent, and every query adds its time
+// to all the scopes that were open while it ran.
+//
+// Queries are double buffered: the results from a frame are read two frames
+// later, by when they are almost always ready, so reading doesn't stall.
+#[derive(Default)]
+struct GpuTimer {
+    free: Vec<GLuint>,
+    frames: [Vec<Segment>; 2],
+    active: bool,
+}
+
+struct Segment {
+    query: GLuint,
+    scopes: Vec<(u64, &'static str)>,
+}
+
+// Where a scope ran within its frame's GPU work
+struct GpuScope {
+    name: &'static str,
+    offset: Duration,
+    duration: Duration,
+}
+
+impl Profiler {
+    pub fn new(options: &Options) -> Profiler {
+        Profiler {
+            enabled: options.profile || options.trace.is_some(),
+            report: options.profile,
+            frame: 0,
+            frame_start: None,
+            submitted: [None; 2],
+            last_report: Instant::now(),
+            next_id: 0,
+            open: Vec::new(),
+            trace: options.trace.clone().map(Trace::new),
+            gpu: GpuTimer::default(),
+            cpu_samples: BTreeMap::new(),
+            gpu_samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn begin(&mut self, name: &'static str) {
+        if !self.enabled {
+            return;
+        }
+
+        let slot = self.frame % 2;
+        self.gpu.stop();
+
+        self.next_id += 1;
+        self.open.push(OpenScope {
+            id: self.next_id,
+            name,
+            start: Instant::now(),
+        });
+
+        self.gpu.start(slot, &self.open);
+    }
+
+    pub fn end(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        let slot = self.frame % 2;
+        self.gpu.stop();
+
+        let Some(scope) = self.open.pop() else {
+            return;
+        };
+
+        let time = scope.start.elapsed();
+        record(&mut self.cpu_samples, scope.name, time);
+
+        if let Some(trace) = &mut self.trace {
+            trace.cpu(scope.name, scope.start, time);
+        }
+
+        if !self.open.is_empty() {
+            self.gpu.start(slot, &self.open);
+        }
+    }
+
+    // Called by the runner before anything else in the frame
+    pub(crate) fn begin_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+
+        if let Some(start) = self.frame_start {
+            record(&mut self.cpu_samples, "frame", now - start);
+
+            if let Some(trace) = &mut self.trace {
+                trace.cpu("frame", start, now - start);
+            }
+        }
+
+        self.frame_start = Some(now);
+        self.frame += 1;
+
+        // These queries were issued two frames ago and are about to be reused
+        let slot = self.frame % 2;
+        let scopes = self.gpu.collect(slot);
+        let mut times: BTreeMap<&'static str, Duration> = BTreeMap::new();
+
+        for scope in &scopes {
+            *times.entry(scope.name).or_default() += scope.duration;
+        }
+
+        for (name, time) in times {
+            record(&mut self.gpu_samples, name, time);
+        }
+
+        if let (Some(trace), Some(submitted)) = (&mut self.trace, self.submitted[slot]) {
+            for scope in &scopes {
+                trace.gpu(scope.name, submitted, scope.offset, scope.duration);
+            }
+        }
+
+        self.submitted[slot] = Some(now);
+
+        if self.report && now - self.last_report >= REPORT_INTERVAL {
+            self.last_report = now;
+            self.report();
+        }
+    }
+
+    // Prints the statistics one last time and deletes the queries, call
+    // while the context is still alive
+    pub(crate) fn finish(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.report {
+            self.report();
+        }
+
+        // Queries from the last two frames are left unread
+        self.gpu.delete();
+
+        if let Some(trace) = &self.trace {
+            trace.write();
+        }
+    }
+
+    pub fn cpu_stats(&self, name: &str) -> Option<Stats> {
+        self.cpu_samples.get(name).and_then(Samples::stats)
+    }
+
+    pub fn gpu_stats(&self, name: &str) -> Option<Stats> {
+        self.gpu_samples.get(name).and_then(Samples::stats)
+    }
+
+    fn report(&self) {
+        if let Some(frame) = self.cpu_stats("frame") {
+            info!("{:<12}     {}", "frame", frame);
+        }
+
+        for name in self.cpu_samples.keys().filter(|name| **name != "frame") {
+            if let Some(stats) = self.cpu_stats(name) {
+                info!("{:<12} cpu {}", name, stats);
+            }
+
+            if let Some(stats) = self.gpu_stats(name) {
+                info!("{:<12} gpu {}", name, stats);
+            }
+        }
+    }
+}
+
+impl GpuTimer {
+    fn start(&mut self, slot: usize, open: &[OpenScope]) {
+        let query = self.free.pop().unwrap_or_else(|| {
+            let mut query = 0;
+            unsafe { gl::GenQueries(1, &mut query) };
+            query
+        });
+
+        unsafe { gl::BeginQuery(gl::TIME_ELAPSED, query) };
+
+        self.frames[slot].push(Segment {
+            query,
+            scopes: open.iter().map(|scope| (scope.id, scope.name)).collect(),
+        });
+        self.active = true;
+    }
+
+    fn stop(&mut self) {
+        if self.active {
+            unsafe { gl::EndQuery(gl::TIME_ELAPSED) };
+            self.active = false;
+        }
+    }
+
+    // Lays the segments out back to back, so each scope spans from its first
+    // segment to its last. If any result isn't ready the whole frame is
+    // dropped rather than waited for.
+    fn collect(&mut self, slot: usize) -> Vec<GpuScope> {
+        let mut scopes: BTreeMap<u64, GpuScope> = BTreeMap::new();
+        let mut cursor = Duration::ZERO;
+        let mut complete = true;
+
+        for segment in self.frames[slot].drain(..) {
+            let mut available = 0;
+            unsafe {
+                gl::GetQueryObjectuiv(segment.query, gl::QUERY_RESULT_AVAILABLE, &mut available)
+            };
+
+            if available == 0 {
+                complete = false;
+            } else if complete {
+                let mut nanoseconds: GLuint64 = 0;
+                unsafe {
+                    gl::GetQueryObjectui64v(segment.query, gl::QUERY_RESULT, &mut nanoseconds)
+                };
+
+                let end = cursor + Duration::from_nanos(nanoseconds);
+
+                for (id, name) in segment.scopes {
+                    let scope = scopes.entry(id).or_insert(GpuScope {
+                        name,
+                        offset: cursor,
+                        duration: Duration::ZERO,
+                    });
+                    scope.duration = end - scope.offset;
+                }
+
+                cursor = end;
+            }
+
+            self.free.push(segment.query);
+        }
+
+        if !complete {
+            return Vec::new();
+        }
+
+        // In the order the scopes began
+        scopes.into_values().collect()
+    }
+
+    fn delete(&mut self) {
+        let segments = self.frames.iter_mut().flat_map(|frame| frame.drain(..));
+        let queries: Vec<GLuint> = segments
+            .map(|segment| segment.query)
+            .chain(self.free.drain(..))
+            .collect();
+
+        unsafe { gl::DeleteQueries(queries.len() as GLsizei, queries.as_ptr()) };
+    }
+}
+
+fn record(samples: &mut BTreeMap<&'static str, Samples>, name: &'static str, time: Duration) {
+    samples.entry(name).or_default().push(time);
+}
+
+// The last WINDOW times of a scope in milliseconds
+#[derive(Default)]
+struct Samples(VecDeque<f32>);
+
+impl Samples {
+    fn push(&mut self, time: Duration) {
+        if self.0.len() == WINDOW {
+            self.0.pop_front();
+        }
+
+        self.0.push_back(time.as_secs_f32() * 1000.0);
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<f32> = self.0.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+
+        // Nearest rank
+        let percentile = |p: f32| {
+            let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Some(Stats {
+            min: sorted[0],
+            avg: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            max: sorted[sorted.len() - 1],
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
+}
+
+// In milliseconds
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p95: f32,
+    pub p99: f32,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "avg {:7.3} ms  min {:7.3}  max {:7.3}  p50 {:7.3}  p95 {:7.3}  p99 {:7.3}",
+            self.avg, self.min, self.max, self.p50, self.p95, self.p99
+        )
+    }
+}
@@ -0,0 +1,169 @@
+use std::ffi::CString;
+
+use gl::types::{GLchar, GLenum, GLint, GLuint};
+use log::warn;
+use nalgebra_glm as glm;
+
+use crate::assets::Assets;
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+
+// A shader program for what common draws itself, like the skybox, and for
+// the chapters past the tutorial's lessons. The earlier chapters have their
+// own Shader that follows the tutorial.
+pub struct Program {
+    id: GLuint,
+    context: ContextId,
+}
+
+impl Program {
+    pub fn new(name: &str, vertex: &str, fragment: &str) -> Result<Program, String> {
+        let context = context::expect_current("shader program");
+
+        let vertex = compile(name, gl::VERTEX_SHADER, vertex)?;
+        let fragment = match compile(name, gl::FRAGMENT_SHADER, fragment) {
+            Ok(fragment) => fragment,
+            Err(error) => {
+                unsafe { gl::DeleteShader(vertex) };
+                return Err(error);
+            }
+        };
+
+        unsafe {
+            let id = gl::CreateProgram();
+            gl::AttachShader(id, vertex);
+            gl::AttachShader(id, fragment);
+            gl_check!(gl::LinkProgram(id));
+
+            // The program keeps what it needs
+            gl::DeleteShader(vertex);
+            gl::DeleteShader(fragment);
+
+            let mut success = 0;
+            gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
+
+            if success == 0 {
+                let mut length = 0;
+                gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut length);
+                let mut log = vec![0u8; length.max(1) as usize];
+                gl::GetProgramInfoLog(
+                    id,
+                    length,
+                    std::ptr::null_mut(),
+                    log.as_mut_ptr() as *mut GLchar,
+                );
+                gl::DeleteProgram(id);
+
+                return Err(format!(
+                    "Could not link {}: {}",
+                    name,
+                    String::from_utf8_lossy(&log).trim_end_matches('\0')
+                ));
+            }
+
+            debug::label(gl::PROGRAM, id, name);
+            Ok(Program { id, context })
+        }
+    }
+
+    // Loads both stages through the assets and names the program after them,
+    // "cubemaps.vs + cubemaps.fs"
+    pub fn from_assets(
+        assets: &mut Assets,
+        vertex_path: &str,
+        fragment_path: &str,
+    ) -> Result<Program, String> {
+        let name = format!("{} + {}", file_name(vertex_path), file_name(fragment_path));
+        let vertex = assets.text(vertex_path)?;
+        let fragment = assets.text(fragment_path)?;
+
+        Program::new(&name, &vertex, &fragment)
+    }
+
+    pub fn use_program(&self) {
+        unsafe { gl_check!(gl::UseProgram(self.id)) };
+    }
+
+    // The program has to be in use
+    pub fn set_int(&self, name: &str, value: i32) {
+        unsafe { gl_check!(gl::Uniform1i(self.location(name), value)) };
+    }
+
+    pub fn set_mat4(&self, name: &str, value: &glm::Mat4) {
+        unsafe {
+            gl_check!(gl::UniformMatrix4fv(
+                self.location(name),
+                1,
+                gl::FALSE,
+                value.as_ptr()
+            ))
+        };
+    }
+
+    fn location(&self, name: &str) -> GLint {
+        let name = CString::new(name).unwrap();
+        unsafe { gl::GetUniformLocation(self.id, name.as_ptr()) }
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe { gl::DeleteProgram(self.id) };
+        } else {
+            warn!(
+                "Leaking program {}, its OpenGL context is not current",
+                self.id
+            );
+        }
+    }
+}
+
+fn compile(name: &str, kind: GLenum, source: &str) -> Result<GLuint, String> {
+    let source = CString::new(source).map_err(|error| error.to_string())?;
+
+    unsafe {
+        let shader = gl::CreateShader(kind);
+        gl::ShaderSource(shader, 1, &source.as_ptr(), std::ptr::null());
+        gl_check!(gl::CompileShader(shader));
+
+        let mut success = 0;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+
+        if success == 0 {
+            let mut length = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+            let mut log = vec![0u8; length.max(1) as usize];
+            gl::GetShaderInfoLog(
+                shader,
+                length,
+                std::ptr::null_mut(),
+                log.as_mut_ptr() as *mut GLchar,
+            );
+            gl::DeleteShader(shader);
+
+            let stage = if kind == gl::VERTEX_SHADER {
+                "vertex"
+            } else {
+                "fragment"
+            };
+
+            return Err(format!(
+                "Could not compile the {} shader of {}: {}",
+                stage,
+                name,
+                String::from_utf8_lossy(&log).trim_end_matches('\0')
+            ));
+        }
+
+        Ok(shader)
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use log::info;
+
+// 1 is the best quality and slowest, 30 the fastest
+const GIF_SPEED: i32 = 10;
+
+enum Target {
+    Gif(Box<GifEncoder<BufWriter<File>>>),
+    // A folder the frames are written to as 0001.png, 0002.png...
+    Png(PathBuf),
+}
+
+// Writes every rendered frame to an animated GIF or a numbered PNG sequence.
+// The runner steps time by a fixed amount while recording so the result
+// plays back at `fps` no matter how long each frame took to render.
+pub struct Recorder {
+    target: Target,
+    path: PathBuf,
+    fps: f32,
+    frames: u32,
+}
+
+impl Recorder {
+    // A path ending in .gif records a GIF, anything else is a folder for PNGs
+    pub fn new(path: &Path, fps: f32) -> Result<Recorder, String> {
+        if !fps.is_finite() || fps < 1.0 {
+            return Err(format!("Can't record at {} frames per second", fps));
+        }
+
+        let is_gif = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+
+        let target = if is_gif {
+            let file = File::create(path)
+                .map_err(|error| format!("Could not create {}: {}", path.display(), error))?;
+
+            // Speed 1 quantizes best but takes about a second a frame
+            let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), GIF_SPEED);
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(|error| error.to_string())?;
+
+            Target::Gif(Box::new(encoder))
+        } else {
+            std::fs::create_dir_all(path)
+                .map_err(|error| format!("Could not create {}: {}", path.display(), error))?;
+
+            Target::Png(path.to_path_buf())
+        };
+
+        Ok(Recorder {
+            target,
+            path: path.to_path_buf(),
+            fps,
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn record(&mut self, image: RgbaImage) -> Result<(), String> {
+        self.frames += 1;
+
+        match &mut self.target {
+            Target::Gif(encoder) => {
+                // GIF delays are in hundredths of a second so 60 fps ends up
+                // a bit slower, 50 or 25 fps play back exactly
+                let delay =
+                    Delay::from_saturating_duration(Duration::from_secs_f32(1.0 / self.fps));
+
+                encoder
+                    .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                    .map_err(|error| error.to_string())
+            }
+            Target::Png(folder) => {
+                let path = folder.join(format!("{:04}.png", self.frames));
+
+                image
+                    .save(&path)
+                    .map_err(|error| format!("Could not save {}: {}", path.display(), error))
+            }
+        }
+    }
+
+    pub fn finish(self) {
+        // Dropping the GIF encoder writes the trailer
+        drop(self.target);
+
+        info!("Recorded {} frames to {}", self.frames, self.path.display());
+    }
+}
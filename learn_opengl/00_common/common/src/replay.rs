@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+
+use glfw::{Key, WindowEvent};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::input::Input;
+
+// Everything the chapter saw from the keyboard and mouse during a run, one
+// entry per frame. Fed back with the same clock times the chapter takes the
+// same camera path and renders the same frames.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    // Framebuffer size, a replay renders at it so the aspect ratio matches
+    pub size: (u32, u32),
+    pub frames: Vec<InputFrame>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub frame: u32,
+    // What the runner passed to Clock::tick
+    pub time: f32,
+    pub events: Vec<WindowEvent>,
+    // The input state after the events, only used to notice when a replay
+    // stops matching the recording
+    pub keys: Vec<Key>,
+    pub mouse_offset: (f32, f32),
+}
+
+// Only the events that change Input or the chapter are recorded. Moving or
+// resizing the window is left to the window doing the replay.
+pub fn is_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::Key(..)
+            | WindowEvent::MouseButton(..)
+            | WindowEvent::CursorPos(..)
+            | WindowEvent::CursorEnter(..)
+            | WindowEvent::Scroll(..)
+            | WindowEvent::Char(..)
+            | WindowEvent::CharModifiers(..)
+            | WindowEvent::Focus(..)
+    )
+}
+
+// Collects the frames of a run and writes them out when the chapter exits
+pub struct InputRecorder {
+    path: PathBuf,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub fn new(path: &Path, size: (u32, u32)) -> InputRecorder {
+        InputRecorder {
+            path: path.to_path_buf(),
+            recording: InputRecording {
+                size,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    // Call once the frame's events have been handled
+    pub fn record(&mut self, time: f32, events: &[WindowEvent], input: &Input) {
+        self.recording.frames.push(InputFrame {
+            frame: self.recording.frames.len() as u32,
+            time,
+            events: events
+                .iter()
+                .filter(|event| is_input(event))
+                .cloned()
+                .collect(),
+            keys: input.keys_down(),
+            mouse_offset: input.mouse_offset(),
+        });
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(&self.recording, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+
+        std::fs::write(&self.path, text)
+            .map_err(|error| format!("Could not write {}: {}", self.path.display(), error))?;
+
+        info!(
+            "Recorded {} frames of input to {}",
+            self.recording.frames.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+// Hands a recording back to the runner a frame at a time
+pub struct InputReplay {
+    recording: InputRecording,
+    next: usize,
+    diverged: bool,
+}
+
+impl InputReplay {
+    pub fn load(path: &Path) -> Result<InputReplay, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+
+        let recording: InputRecording = ron::from_str(&text)
+            .map_err(|error| format!("Could not parse {}: {}", path.display(), error))?;
+
+        Ok(InputReplay {
+            recording,
+            next: 0,
+            diverged: false,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.recording.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.recording.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recording.frames.is_empty()
+    }
+
+    // None once the recording has run out
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        let frame = self.recording.frames.get(self.next)?;
+        self.next += 1;
+        Some(frame)
+    }
+
+    // Compares the input state with the recording after the last frame's
+    // events were handled. Only the first difference is reported, everything
+    // after it is likely off too.
+    pub fn check(&mut self, input: &Input) {
+        if self.diverged || self.next == 0 {
+            return;
+        }
+
+        let frame = &self.recording.frames[self.next - 1];
+
+        if frame.keys != input.keys_down() || frame.mouse_offset != input.mouse_offset() {
+            warn!(
+                "Replay no longer matches the recording from frame {}",
+                frame.frame
+            );
+            self.diverged = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Action, Modifiers};
+
+    use super::*;
+
+    fn press(key: Key) -> WindowEvent {
+        WindowEvent::Key(key, 0, Action::Press, Modifiers::empty())
+    }
+
+    fn replay_of(frames: Vec<InputFrame>) -> InputReplay {
+        InputReplay {
+            recording: InputRecording {
+                size: (400, 300),
+                frames,
+            },
+            next: 0,
+            diverged: false,
+        }
+    }
+
+    #[test]
+    fn only_input_events_are_recorded() {
+        assert!(is_input(&press(Key::W)));
+        assert!(is_input(&WindowEvent::CursorPos(1.0, 2.0)));
+        assert!(!is_input(&WindowEvent::Size(800, 600)));
+        assert!(!is_input(&WindowEvent::Pos(10, 10)));
+    }
+
+    #[test]
+    fn a_recording_plays_back_frame_by_frame() {
+        let path = std::env::temp_dir().join(format!("replay-{}.ron", std::process::id()));
+        let mut recorder = InputRecorder::new(&path, (400, 300));
+        let mut input = Input::new();
+
+        let events = [
+            press(Key::W),
+            WindowEvent::Size(800, 600),
+            WindowEvent::CursorPos(10.0, 10.0),
+            WindowEvent::CursorPos(15.0, 5.0),
+        ];
+        for event in &events {
+            input.handle_event(event);
+        }
+        recorder.record(0.5, &events, &input);
+        input.end_frame();
+        recorder.record(1.0, &[], &input);
+        recorder.finish().unwrap();
+
+        let mut replay = InputReplay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(replay.size(), (400, 300));
+        assert_eq!(replay.len(), 2);
+
+        let first = replay.next_frame().unwrap();
+        assert_eq!((first.frame, first.time), (0, 0.5));
+        // The resize is left out
+        assert_eq!(first.events.len(), 3);
+        assert_eq!(first.keys, [Key::W]);
+        assert_eq!(first.mouse_offset, (5.0, 5.0));
+
+        let second = replay.next_frame().unwrap();
+        assert_eq!((second.frame, second.time), (1, 1.0));
+        assert!(second.events.is_empty());
+
+        assert!(replay.next_frame().is_none());
+    }
+
+    #[test]
+    fn notices_when_the_input_stops_matching() {
+        let frame = |frame, keys| InputFrame {
+            frame,
+            time: frame as f32,
+            events: Vec::new(),
+            keys,
+            mouse_offset: (0.0, 0.0),
+        };
+        let mut replay = replay_of(vec![frame(0, vec![Key::W]), frame(1, Vec::new())]);
+        let mut input = Input::new();
+
+        // Nothing to compare against before the first frame
+        replay.check(&input);
+        assert!(!replay.diverged);
+
+        input.handle_event(&press(Key::W));
+        replay.next_frame();
+        replay.check(&input);
+        assert!(!replay.diverged);
+
+        // W is still down but the recording let go of it
+        replay.next_frame();
+        replay.check(&input);
+        assert!(replay.diverged);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::{error, info, warn};
+use nalgebra_glm as glm;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::Assets;
+
+// The values a chapter would otherwise compile in, read from a RON file like
+// assets/scene.ron. Anything left out of the file keeps its default.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub clear_color: [f32; 4],
+    pub camera: CameraStart,
+    pub lights: Vec<Light>,
+    pub materials: HashMap<String, Material>,
+    pub objects: Vec<Transform>,
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene {
+            clear_color: [0.1, 0.1, 0.1, 1.0],
+            camera: CameraStart::default(),
+            lights: Vec::new(),
+            materials: HashMap::new(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl Scene {
+    // Missing lights and materials fall back to the defaults so a half
+    // edited file doesn't take the chapter down
+    pub fn light(&self, index: usize) -> Light {
+        self.lights.get(index).cloned().unwrap_or_default()
+    }
+
+    pub fn material(&self, name: &str) -> Material {
+        self.materials.get(name).cloned().unwrap_or_default()
+    }
+}
+
+// Where the camera is when the chapter starts, only read once. The session
+// saves the same thing when the chapter closes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CameraStart {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+}
+
+impl Default for CameraStart {
+    fn default() -> CameraStart {
+        CameraStart {
+            position: [0.0, 0.0, 3.0],
+            yaw: -90.0,
+            pitch: 0.0,
+            fov: 45.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Light {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+}
+
+impl Default for Light {
+    fn default() -> Light {
+        Light {
+            position: [1.2, 1.0, 2.0],
+            color: [1.0, 1.0, 1.0],
+            ambient: [0.2, 0.2, 0.2],
+            diffuse: [0.5, 0.5, 0.5],
+            specular: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    // The flat objectColor from before the chapters had real materials
+    pub color: [f32; 3],
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            color: [1.0, 1.0, 1.0],
+            ambient: [1.0, 1.0, 1.0],
+            diffuse: [1.0, 1.0, 1.0],
+            specular: [0.5, 0.5, 0.5],
+            shininess: 32.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub position: [f32; 3],
+    pub axis: [f32; 3],
+    // In degrees
+    pub angle: f32,
+    pub scale: [f32; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform {
+            position: [0.0, 0.0, 0.0],
+            axis: [0.0, 1.0, 0.0],
+            angle: 0.0,
+            scale: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl Transform {
+    pub fn position(&self) -> glm::Vec3 {
+        self.position.into()
+    }
+
+    // Translate, rotate then scale like the chapters build their models
+    pub fn model(&self) -> glm::Mat4 {
+        let mut model = glm::Mat4::identity();
+        model = glm::translate(&model, &self.position.into());
+        model = glm::rotate(&model, self.angle.to_radians(), &self.axis.into());
+        glm::scale(&model, &self.scale.into())
+    }
+}
+
+// A scene loaded from disk that picks up edits to the file while the chapter
+// is running
+pub struct SceneFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    scene: Scene,
+}
+
+impl SceneFile {
+    // The file has to be there and parse when the chapter starts
+    pub fn load<P: AsRef<Path>>(path: P) -> SceneFile {
+        let path = path.as_ref().to_path_buf();
+
+        let scene = read_scene(&path).unwrap_or_else(|error| {
+            error!("{}", error);
+            std::process::exit(1);
+        });
+
+        SceneFile {
+            modified: modified_time(&path),
+            path,
+            scene,
+        }
+    }
+
+    // Found through the assets. An embedded scene is only reloaded when there
+    // is also a file at its path and it changes.
+    pub fn from_assets<P: AsRef<Path>>(assets: &Assets, path: P) -> SceneFile {
+        let (source, scene) = assets
+            .find(path.as_ref())
+            .and_then(|source| {
+                let data = source.read()?;
+                let text = String::from_utf8_lossy(&data);
+                parse_scene(source.path(), &text).map(|scene| (source, scene))
+            })
+            .unwrap_or_else(|error| {
+                error!("{}", error);
+                std::process::exit(1);
+            });
+
+        let path = source.path().to_path_buf();
+
+        SceneFile {
+            modified: modified_time(&path),
+            path,
+            scene,
+        }
+    }
+
+    // Call once a frame, returns true when the scene changed. A file that
+    // doesn't parse is reported and the last good scene is kept.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+
+        match read_scene(&self.path) {
+            Ok(scene) => {
+                info!("Reloaded {}", self.path.display());
+                self.scene = scene;
+                true
+            }
+            Err(error) => {
+                warn!("{}", error);
+                false
+            }
+        }
+    }
+}
+
+impl Deref for SceneFile {
+    type Target = Scene;
+
+    fn deref(&self) -> &Scene {
+        &self.scene
+    }
+}
+
+fn read_scene(path: &Path) -> Result<Scene, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+
+    parse_scene(path, &text)
+}
+
+fn parse_scene(path: &Path, text: &str) -> Result<Scene, String> {
+    ron::from_str(text).map_err(|error| format!("Could not parse {}: {}", path.display(), error))
+}
+
+pub(crate) fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
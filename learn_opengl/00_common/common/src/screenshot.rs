@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gl::types::GLint;
+use image::RgbaImage;
+use log::{error, info};
+
+use crate::app::{App, Context};
+use crate::framebuffer::{self, Framebuffer};
+
+// Reads back what was just rendered to the window, call before swapping
+pub fn capture_window(width: u32, height: u32) -> RgbaImage {
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::ReadBuffer(gl::BACK);
+    }
+
+    framebuffer::read_pixels(width, height)
+}
+
+// Renders the frame again into a framebuffer `scale` times the size of the
+// window and reads that back instead
+pub fn capture_supersampled<A: App>(
+    app: &mut A,
+    ctx: &mut Context,
+    width: u32,
+    height: u32,
+    scale: u32,
+) -> RgbaImage {
+    let mut max_size: GLint = 0;
+    unsafe {
+        gl::GetIntegerv(gl::MAX_RENDERBUFFER_SIZE, &mut max_size);
+    }
+
+    let largest = width.max(height);
+    let scale = scale.min(max_size as u32 / largest).max(1);
+
+    let framebuffer = Framebuffer::new(width * scale, height * scale);
+    framebuffer.bind();
+
+    app.render(ctx);
+
+    let image = framebuffer.read_pixels();
+
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, width as GLint, height as GLint);
+    }
+
+    image
+}
+
+// Saves to screenshot_<date>_<time>.png in the working directory
+pub fn save(image: &RgbaImage) {
+    let path = timestamped_path();
+
+    match image.save(&path) {
+        Ok(()) => info!("Saved {}", path.display()),
+        Err(error) => error!("Could not save {}: {}", path.display(), error),
+    }
+}
+
+fn timestamped_path() -> PathBuf {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time_of_day = seconds % 86400;
+
+    PathBuf::from(format!(
+        "screenshot_{:04}-{:02}-{:02}_{:02}-{:02}-{:02}_{:03}.png",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_millis()
+    ))
+}
+
+// Days since 1970-01-01 to a UTC year, month and day, from
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::display::WindowMode;
+use crate::scene::CameraStart;
+
+// What a chapter looked like when it was closed, so the next run picks up
+// where it left off. Kept next to the binary in <binary>.session.ron, one per
+// chapter, and ignored with --no-session, --headless, --record-input or
+// --replay so those always start the same.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub window: Option<WindowState>,
+    pub wireframe: bool,
+    pub camera: Option<CameraStart>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct WindowState {
+    pub mode: WindowMode,
+    pub monitor: usize,
+    // The window's position and size when it isn't fullscreen
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+}
+
+impl Session {
+    // A missing or broken file is a fresh start
+    pub fn load() -> Session {
+        let Some(path) = path() else {
+            return Session::default();
+        };
+
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Session::default();
+        };
+
+        ron::from_str(&text).unwrap_or_else(|error| {
+            warn!("Ignoring {}: {}", path.display(), error);
+            Session::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|text| std::fs::write(&path, text).map_err(|error| error.to_string()));
+
+        match result {
+            Ok(()) => debug!("Saved {}", path.display()),
+            Err(error) => warn!("Could not save {}: {}", path.display(), error),
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let name = exe.file_stem()?.to_string_lossy().into_owned();
+
+    Some(exe.with_file_name(format!("{}.session.ron", name)))
+}
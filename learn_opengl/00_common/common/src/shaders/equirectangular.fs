@@ -0,0 +1,40 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 Position;
+
+uniform sampler2D equirectangularMap;
+// 0 to 5, +X -X +Y -Y +Z -Z like GL_TEXTURE_CUBE_MAP_POSITIVE_X + face
+uniform int face;
+
+const vec2 invAtan = vec2(0.1591, 0.3183);
+
+// The direction a texel of the face is looked up with, from the cube map
+// face table in the OpenGL spec
+vec3 direction(vec2 p)
+{
+    switch (face) {
+        case 0: return vec3(1.0, -p.y, -p.x);
+        case 1: return vec3(-1.0, -p.y, p.x);
+        case 2: return vec3(p.x, 1.0, p.y);
+        case 3: return vec3(p.x, -1.0, -p.y);
+        case 4: return vec3(p.x, -p.y, 1.0);
+        default: return vec3(-p.x, -p.y, -1.0);
+    }
+}
+
+vec2 sampleSphericalMap(vec3 v)
+{
+    vec2 uv = vec2(atan(v.z, v.x), asin(v.y));
+    uv *= invAtan;
+    uv += 0.5;
+    return uv;
+}
+
+void main()
+{
+    vec2 uv = sampleSphericalMap(normalize(direction(Position)));
+    // Level 0, the jump in uv where the panorama wraps around would pick a
+    // tiny mipmap and leave a seam
+    FragColor = vec4(textureLod(equirectangularMap, uv, 0.0).rgb, 1.0);
+}
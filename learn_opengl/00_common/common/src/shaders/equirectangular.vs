@@ -0,0 +1,9 @@
+#version 330 core
+// One triangle that covers the whole face, no vertex buffer needed
+out vec2 Position;
+
+void main()
+{
+    Position = vec2(float((gl_VertexID & 1) << 2) - 1.0, float((gl_VertexID & 2) << 1) - 1.0);
+    gl_Position = vec4(Position, 0.0, 1.0);
+}
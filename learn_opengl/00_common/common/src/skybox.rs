@@ -0,0 +1,125 @@
+use gl::types::GLenum;
+use nalgebra_glm as glm;
+
+use crate::buffer::{Buffer, Usage};
+use crate::cubemap::Cubemap;
+use crate::program::Program;
+use crate::vertex_array::VertexArray;
+
+// Draws a cube map around the camera, as far away as anything can be. Draw
+// it after everything else so the depth test skips the pixels that are
+// covered.
+//
+//     let skybox = Skybox::new();
+//     ...
+//     skybox.draw(&sky, &view, &projection);
+pub struct Skybox {
+    program: Program,
+    vao: VertexArray,
+    // Only kept so it lives as long as the vertex array that reads it
+    _vbo: Buffer<f32>,
+}
+
+impl Skybox {
+    pub fn new() -> Skybox {
+        let program = Program::new(
+            "skybox",
+            include_str!("shaders/skybox.vs"),
+            include_str!("shaders/skybox.fs"),
+        )
+        .unwrap_or_else(|error| panic!("{}", error));
+
+        #[rustfmt::skip]
+        let vertices = [
+            // positions
+            -1.0f32,  1.0, -1.0,
+            -1.0, -1.0, -1.0,
+             1.0, -1.0, -1.0,
+             1.0, -1.0, -1.0,
+             1.0,  1.0, -1.0,
+            -1.0,  1.0, -1.0,
+
+            -1.0, -1.0,  1.0,
+            -1.0, -1.0, -1.0,
+            -1.0,  1.0, -1.0,
+            -1.0,  1.0, -1.0,
+            -1.0,  1.0,  1.0,
+            -1.0, -1.0,  1.0,
+
+             1.0, -1.0, -1.0,
+             1.0, -1.0,  1.0,
+             1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,
+             1.0,  1.0, -1.0,
+             1.0, -1.0, -1.0,
+
+            -1.0, -1.0,  1.0,
+            -1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,
+             1.0, -1.0,  1.0,
+            -1.0, -1.0,  1.0,
+
+            -1.0,  1.0, -1.0,
+             1.0,  1.0, -1.0,
+             1.0,  1.0,  1.0,
+             1.0,  1.0,  1.0,
+            -1.0,  1.0,  1.0,
+            -1.0,  1.0, -1.0,
+
+            -1.0, -1.0, -1.0,
+            -1.0, -1.0,  1.0,
+             1.0, -1.0, -1.0,
+             1.0, -1.0, -1.0,
+            -1.0, -1.0,  1.0,
+             1.0, -1.0,  1.0,
+        ];
+
+        let vbo = Buffer::vertices(&vertices, Usage::Static);
+        vbo.label("skybox_vbo");
+
+        let vao = VertexArray::new();
+        vao.label("skybox_vao");
+        vao.attribute(&vbo, 0, 3, 3, 0);
+        VertexArray::unbind();
+
+        program.use_program();
+        program.set_int("skybox", 0);
+
+        Skybox {
+            program,
+            vao,
+            _vbo: vbo,
+        }
+    }
+
+    // The camera's view matrix, only its rotation is used so the sky never
+    // gets closer. Uses texture unit 0 and leaves the depth test the way it
+    // was.
+    pub fn draw(&self, cubemap: &Cubemap, view: &glm::Mat4, projection: &glm::Mat4) {
+        let view = glm::mat3_to_mat4(&glm::mat4_to_mat3(view));
+        let mut depth_func = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::DEPTH_FUNC, &mut depth_func);
+            // The sky is at a depth of exactly 1.0, which the cleared depth
+            // buffer is too and LESS would throw away
+            gl::DepthFunc(gl::LEQUAL);
+        }
+
+        self.program.use_program();
+        self.program.set_mat4("view", &view);
+        self.program.set_mat4("projection", projection);
+        cubemap.bind(0);
+        self.vao.draw_arrays(gl::TRIANGLES, 0, 36);
+        VertexArray::unbind();
+
+        unsafe { gl::DepthFunc(depth_func as GLenum) };
+    }
+}
+
+impl Default for Skybox {
+    fn default() -> Skybox {
+        Skybox::new()
+    }
+}
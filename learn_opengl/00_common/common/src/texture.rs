@@ -0,0 +1,439 @@
+use std::cell::Cell;
+use std::path::Path;
+
+use gl::types::{GLenum, GLfloat, GLint, GLsizei, GLuint};
+use image::DynamicImage;
+use log::{debug, warn};
+
+use crate::buffer::{Buffer, Usage};
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+
+// Not in the gl crate's 4.5 bindings, core in 4.6 and the same values as
+// GL_EXT_texture_filter_anisotropic
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl Wrap {
+    pub(crate) fn gl_enum(self) -> GLenum {
+        match self {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+// How a texture is sampled. The default is what the tutorial sets up most of
+// the time, repeating and linear with linear mipmaps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampler {
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    // Filtering between mipmap levels, None doesn't make mipmaps at all
+    pub mipmaps: Option<Filter>,
+    // 1 is off, clamped to what the driver supports
+    pub anisotropy: f32,
+}
+
+impl Default for Sampler {
+    fn default() -> Sampler {
+        Sampler {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmaps: Some(Filter::Linear),
+            anisotropy: 1.0,
+        }
+    }
+}
+
+impl Sampler {
+    // Both directions at once
+    pub fn wrap(mut self, wrap: Wrap) -> Sampler {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    fn min_filter_enum(&self) -> GLenum {
+        match (self.min_filter, self.mipmaps) {
+            (Filter::Nearest, None) => gl::NEAREST,
+            (Filter::Linear, None) => gl::LINEAR,
+            (Filter::Nearest, Some(Filter::Nearest)) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Nearest, Some(Filter::Linear)) => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear, Some(Filter::Nearest)) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Linear, Some(Filter::Linear)) => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    fn mag_filter_enum(&self) -> GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextureOptions {
+    // Images start at the top row and GL textures at the bottom one, flip to
+    // match what the tutorial gets from stbi_set_flip_vertically_on_load
+    pub flip: bool,
+    // Colors stored with gamma, like photos and most hand painted textures.
+    // Only RGB and RGBA have sRGB formats, anything else ignores it.
+    pub srgb: bool,
+    pub sampler: Sampler,
+}
+
+// A 2D texture deleted when dropped, uploaded in the format the image
+// decoded to instead of always as RGB.
+//
+//     let face = Texture2D::from_file(
+//         "assets/textures/awesomeface.png",
+//         &TextureOptions { flip: true, ..Default::default() },
+//     )
+//     .unwrap();
+//     face.bind(1);
+pub struct Texture2D {
+    id: GLuint,
+    // Cells because a texture loaded in the background is shared while its
+    // image is swapped in
+    width: Cell<u32>,
+    height: Cell<u32>,
+    mipmaps: Cell<bool>,
+    // What set_sampler was last called with, for when the image is replaced
+    sampler: Cell<Sampler>,
+    loading: Cell<bool>,
+    context: ContextId,
+}
+
+impl Texture2D {
+    // Labeled with the file name
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        options: &TextureOptions,
+    ) -> Result<Texture2D, String> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        let texture = Texture2D::from_memory(&data, options)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        if let Some(name) = path.file_name() {
+            texture.label(&name.to_string_lossy());
+        }
+
+        Ok(texture)
+    }
+
+    // An encoded image like the bytes of a PNG or JPEG file, the format is
+    // worked out from the data
+    pub fn from_memory(data: &[u8], options: &TextureOptions) -> Result<Texture2D, String> {
+        let image = image::load_from_memory(data).map_err(|error| error.to_string())?;
+        let texture = Texture2D::from_image(&image, options);
+
+        debug!(
+            "Loaded a {}x{} {:?} texture",
+            texture.width(),
+            texture.height(),
+            image.color()
+        );
+
+        Ok(texture)
+    }
+
+    // The texture is left bound to the active unit
+    pub fn from_image(image: &DynamicImage, options: &TextureOptions) -> Texture2D {
+        let texture = Texture2D::create();
+
+        if options.flip {
+            texture.store(&image.flipv(), options.srgb, None);
+        } else {
+            texture.store(image, options.srgb, None);
+        }
+
+        texture.set_sampler(&options.sampler);
+        texture
+    }
+
+    // A white pixel to draw with until the image has been decoded and
+    // finish_loading puts it in the same texture
+    pub(crate) fn placeholder(options: &TextureOptions) -> Texture2D {
+        let texture = Texture2D::create();
+        let white = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            1,
+            1,
+            image::Rgba([255, 255, 255, 255]),
+        ));
+
+        texture.store(&white, false, None);
+        texture.set_sampler(&options.sampler);
+        texture.loading.set(true);
+        texture
+    }
+
+    // Puts a decoded image in the texture, the placeholder or an older
+    // version of a file that changed. The size can be different. The image
+    // has already been flipped by the loader. Returns the bytes uploaded.
+    pub(crate) fn finish_loading(
+        &self,
+        image: &DynamicImage,
+        options: &TextureOptions,
+        pixel_buffer: bool,
+    ) -> usize {
+        let size = self.store(image, options.srgb, Some(pixel_buffer));
+
+        // The old mipmaps are of the old image
+        self.mipmaps.set(false);
+        self.set_sampler(&self.sampler.get());
+        self.loading.set(false);
+        size
+    }
+
+    // Keeps the placeholder
+    pub(crate) fn failed_loading(&self) {
+        self.loading.set(false);
+    }
+
+    fn create() -> Texture2D {
+        let context = context::expect_current("texture");
+        let mut id = 0;
+
+        unsafe { gl::GenTextures(1, &mut id) };
+
+        Texture2D {
+            id,
+            width: Cell::new(0),
+            height: Cell::new(0),
+            mipmaps: Cell::new(false),
+            sampler: Cell::new(Sampler::default()),
+            loading: Cell::new(false),
+            context,
+        }
+    }
+
+    // Replaces the texture's storage with the image. Some(true) streams it
+    // through a pixel buffer, which lets the driver copy it to the GPU
+    // without holding up the call.
+    fn store(&self, image: &DynamicImage, srgb: bool, pixel_buffer: Option<bool>) -> usize {
+        let upload = Upload::new(image, srgb);
+        let staging = (pixel_buffer == Some(true))
+            .then(|| Buffer::new(gl::PIXEL_UNPACK_BUFFER, &upload.data, Usage::Stream));
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+
+            // Rows of RGB or single channel images aren't 4 byte aligned
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl_check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                upload.internal_format as GLint,
+                image.width() as GLsizei,
+                image.height() as GLsizei,
+                0,
+                upload.format,
+                upload.component_type,
+                // An offset into the bound pixel buffer instead of a pointer
+                match staging {
+                    Some(_) => std::ptr::null(),
+                    None => upload.data.as_ptr().cast(),
+                },
+            ));
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+            if staging.is_some() {
+                gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+            }
+
+            // Grey images are stored in red and green, read them as grey in
+            // the shader like an RGB image would be
+            let swizzle = upload
+                .swizzle
+                .unwrap_or([gl::RED, gl::GREEN, gl::BLUE, gl::ALPHA])
+                .map(|channel| channel as GLint);
+            gl::TexParameteriv(gl::TEXTURE_2D, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+        }
+
+        self.width.set(image.width());
+        self.height.set(image.height());
+        upload.data.len()
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width.get()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height.get()
+    }
+
+    // Still the placeholder of a background load
+    pub fn is_loading(&self) -> bool {
+        self.loading.get()
+    }
+
+    // Binds it to texture unit n, the one a sampler uniform set to n reads
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+
+    pub fn label(&self, name: &str) {
+        debug::label(gl::TEXTURE, self.id, name);
+    }
+
+    // Mipmaps are made the first time a sampler wants them. Leaves the
+    // texture bound to the active unit.
+    pub fn set_sampler(&self, sampler: &Sampler) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            set_parameters(gl::TEXTURE_2D, sampler);
+
+            if sampler.mipmaps.is_some() && !self.mipmaps.get() {
+                gl_check!(gl::GenerateMipmap(gl::TEXTURE_2D));
+                self.mipmaps.set(true);
+            }
+        }
+
+        self.sampler.set(*sampler);
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe { gl::DeleteTextures(1, &self.id) };
+        } else {
+            warn!(
+                "Leaking texture {}, its OpenGL context is not current",
+                self.id
+            );
+        }
+    }
+}
+
+// Wrapping, filtering and anisotropy of the texture bound to target
+pub(crate) fn set_parameters(target: GLenum, sampler: &Sampler) {
+    unsafe {
+        gl::TexParameteri(
+            target,
+            gl::TEXTURE_WRAP_S,
+            sampler.wrap_s.gl_enum() as GLint,
+        );
+        gl::TexParameteri(
+            target,
+            gl::TEXTURE_WRAP_T,
+            sampler.wrap_t.gl_enum() as GLint,
+        );
+        gl::TexParameteri(
+            target,
+            gl::TEXTURE_MIN_FILTER,
+            sampler.min_filter_enum() as GLint,
+        );
+        gl::TexParameteri(
+            target,
+            gl::TEXTURE_MAG_FILTER,
+            sampler.mag_filter_enum() as GLint,
+        );
+    }
+
+    set_anisotropy(target, sampler.anisotropy);
+}
+
+fn set_anisotropy(target: GLenum, anisotropy: f32) {
+    if !debug::has_extension("GL_EXT_texture_filter_anisotropic")
+        && !debug::has_extension("GL_ARB_texture_filter_anisotropic")
+    {
+        if anisotropy > 1.0 {
+            debug!("Anisotropic filtering isn't supported, ignoring it");
+        }
+        return;
+    }
+
+    let mut max: GLfloat = 1.0;
+
+    unsafe {
+        gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        gl::TexParameterf(target, TEXTURE_MAX_ANISOTROPY, anisotropy.min(max));
+    }
+}
+
+// How an image's pixels go into glTexImage2D
+pub(crate) struct Upload<'a> {
+    pub(crate) internal_format: GLenum,
+    pub(crate) format: GLenum,
+    pub(crate) component_type: GLenum,
+    pub(crate) swizzle: Option<[GLenum; 4]>,
+    pub(crate) data: std::borrow::Cow<'a, [u8]>,
+}
+
+impl<'a> Upload<'a> {
+    pub(crate) fn new(image: &'a DynamicImage, srgb: bool) -> Upload<'a> {
+        use image::ColorType;
+
+        let grey = Some([gl::RED, gl::RED, gl::RED, gl::ONE]);
+        let grey_alpha = Some([gl::RED, gl::RED, gl::RED, gl::GREEN]);
+
+        let (internal_format, format, component_type, swizzle) = match image.color() {
+            ColorType::L8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE, grey),
+            ColorType::La8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE, grey_alpha),
+            ColorType::Rgb8 if srgb => (gl::SRGB8, gl::RGB, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgb8 => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgba8 if srgb => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE, None),
+            ColorType::L16 => (gl::R16, gl::RED, gl::UNSIGNED_SHORT, grey),
+            ColorType::La16 => (gl::RG16, gl::RG, gl::UNSIGNED_SHORT, grey_alpha),
+            ColorType::Rgb16 => (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT, None),
+            ColorType::Rgba16 => (gl::RGBA16, gl::RGBA, gl::UNSIGNED_SHORT, None),
+            ColorType::Rgb32F => (gl::RGB32F, gl::RGB, gl::FLOAT, None),
+            ColorType::Rgba32F => (gl::RGBA32F, gl::RGBA, gl::FLOAT, None),
+            other => {
+                // Anything newer image versions decode to goes up as RGBA
+                debug!("Converting {:?} to RGBA to upload it", other);
+                let internal_format = if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 };
+
+                return Upload {
+                    internal_format,
+                    format: gl::RGBA,
+                    component_type: gl::UNSIGNED_BYTE,
+                    swizzle: None,
+                    data: image.to_rgba8().into_raw().into(),
+                };
+            }
+        };
+
+        Upload {
+            internal_format,
+            format,
+            component_type,
+            swizzle,
+            data: image.as_bytes().into(),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// Frame timing, replaces calling glfwGetTime all over the chapters.
+//
+// The runner feeds it the real time once a frame and it hands out simulated
+// time, which can be paused, slowed down or sped up and stepped one fixed
+// update at a time. Animations that only read elapsed and delta freeze and
+// replay exactly with it.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    // Simulated seconds since the last frame, 0 while paused
+    pub delta: f32,
+    // Simulated seconds since the start, stands still while paused
+    pub elapsed: f32,
+    // Seconds since the last frame on the wall clock, keeps going while paused
+    pub real_delta: f32,
+    // Length of every App::fixed_update in simulated seconds
+    pub fixed_delta: f32,
+    // How far the frame is between the last two fixed updates, from 0 to 1.
+    // Render interpolates between the previous and current fixed state with it.
+    pub alpha: f32,
+    scale: f32,
+    paused: bool,
+    step: bool,
+    accumulator: f32,
+    real_elapsed: f32,
+    // Simulated time is worked out from the real time since the last change
+    // of scale or pause rather than summed up frame by frame, so it doesn't
+    // drift and headless frame n is always at n * timestep
+    anchor_real: f32,
+    anchor_elapsed: f32,
+}
+
+// Hz of App::fixed_update
+pub const FIXED_RATE: f32 = 60.0;
+
+// A frame that takes very long (a breakpoint, dragging the window) would
+// otherwise run hundreds of fixed updates to catch up
+const MAX_FIXED_UPDATES: u32 = 8;
+
+const MIN_SCALE: f32 = 1.0 / 64.0;
+const MAX_SCALE: f32 = 64.0;
+
+impl Default for Clock {
+    fn default() -> Clock {
+        Clock {
+            delta: 0.0,
+            elapsed: 0.0,
+            real_delta: 0.0,
+            fixed_delta: 1.0 / FIXED_RATE,
+            alpha: 0.0,
+            scale: 1.0,
+            paused: false,
+            step: false,
+            accumulator: 0.0,
+            real_elapsed: 0.0,
+            anchor_real: 0.0,
+            anchor_elapsed: 0.0,
+        }
+    }
+}
+
+impl Clock {
+    // Advances to the real time now and returns how many fixed updates are
+    // due this frame
+    pub fn tick(&mut self, now: f32) -> u32 {
+        self.real_delta = now - self.real_elapsed;
+        self.real_elapsed = now;
+
+        // A single step moves exactly one fixed update while paused and
+        // leaves the interpolation where it was
+        if self.paused {
+            if self.step {
+                self.anchor_elapsed += self.fixed_delta;
+            }
+
+            self.anchor_real = now;
+            self.delta = self.anchor_elapsed - self.elapsed;
+            self.elapsed = self.anchor_elapsed;
+
+            let updates = self.step as u32;
+            self.step = false;
+            return updates;
+        }
+
+        let elapsed = self.anchor_elapsed + (now - self.anchor_real) * self.scale;
+        self.delta = elapsed - self.elapsed;
+        self.elapsed = elapsed;
+        self.accumulator += self.delta;
+
+        // The small slack keeps a frame exactly one fixed update long from
+        // rounding down to none and then doing two the frame after
+        let mut updates = (self.accumulator / self.fixed_delta + 1e-3) as u32;
+        self.accumulator = (self.accumulator - updates as f32 * self.fixed_delta).max(0.0);
+
+        if updates > MAX_FIXED_UPDATES {
+            updates = MAX_FIXED_UPDATES;
+            self.accumulator = 0.0;
+        }
+
+        self.alpha = self.accumulator / self.fixed_delta;
+        updates
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.reanchor();
+        self.paused = paused;
+        self.step = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+    }
+
+    // Runs one fixed update on the next frame, only does anything while paused
+    pub fn step(&mut self) {
+        if self.paused {
+            self.step = true;
+        }
+    }
+
+    // How many times faster than real time the simulation runs, 0.5 is half speed
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    // Takes effect from the last frame on
+    pub fn set_scale(&mut self, scale: f32) {
+        self.reanchor();
+        self.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+    }
+
+    fn reanchor(&mut self) {
+        self.anchor_real = self.real_elapsed;
+        self.anchor_elapsed = self.elapsed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / FIXED_RATE;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn runs_one_fixed_update_per_step_of_time() {
+        let mut clock = Clock::default();
+        assert_eq!(clock.tick(0.0), 0);
+
+        assert_eq!(clock.tick(STEP), 1);
+        assert!(close(clock.delta, STEP));
+
+        // Half a step is kept for the next frame
+        assert_eq!(clock.tick(2.5 * STEP), 1);
+        assert!(close(clock.alpha, 0.5));
+
+        assert_eq!(clock.tick(3.0 * STEP), 1);
+        assert!(close(clock.alpha, 0.0));
+        assert!(close(clock.elapsed, 3.0 * STEP));
+    }
+
+    #[test]
+    fn long_frames_are_clamped() {
+        let mut clock = Clock::default();
+        clock.tick(0.0);
+
+        assert_eq!(clock.tick(1.0), MAX_FIXED_UPDATES);
+        // The time that couldn't be caught up with is dropped
+        assert_eq!(clock.alpha, 0.0);
+        assert_eq!(clock.tick(1.0 + STEP), 1);
+    }
+
+    #[test]
+    fn pausing_stops_simulated_time() {
+        let mut clock = Clock::default();
+        clock.tick(0.0);
+        clock.tick(1.0);
+        clock.set_paused(true);
+
+        assert_eq!(clock.tick(2.0), 0);
+        assert_eq!(clock.delta, 0.0);
+        assert!(close(clock.elapsed, 1.0));
+        assert!(close(clock.real_delta, 1.0));
+
+        clock.toggle_pause();
+        clock.tick(3.0);
+        assert!(close(clock.elapsed, 2.0));
+    }
+
+    #[test]
+    fn stepping_runs_one_update_while_paused() {
+        let mut clock = Clock::default();
+        clock.tick(0.0);
+
+        // Not paused, nothing to step
+        clock.step();
+        assert_eq!(clock.tick(0.5 * STEP), 0);
+
+        clock.set_paused(true);
+        clock.step();
+        assert_eq!(clock.tick(1.0), 1);
+        assert!(close(clock.delta, STEP));
+        assert_eq!(clock.tick(2.0), 0);
+    }
+
+    #[test]
+    fn scale_speeds_up_and_slows_down() {
+        let mut clock = Clock::default();
+        clock.tick(0.0);
+        clock.set_scale(0.5);
+        clock.tick(2.0);
+        assert!(close(clock.elapsed, 1.0));
+
+        clock.set_scale(1000.0);
+        assert_eq!(clock.scale(), MAX_SCALE);
+        clock.set_scale(0.0);
+        assert_eq!(clock.scale(), MIN_SCALE);
+    }
+}
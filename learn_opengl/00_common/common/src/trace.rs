@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+
+// The CPU and GPU are two threads of one process in the trace viewer
+const CPU: u32 = 1;
+const GPU: u32 = 2;
+
+// Collects every profiled scope of a run and writes them out as a Chrome
+// trace-event file at the end, which chrome://tracing, Perfetto or
+// Speedscope can open
+pub(crate) struct Trace {
+    path: PathBuf,
+    start: Instant,
+    events: Vec<Event>,
+}
+
+struct Event {
+    name: &'static str,
+    thread: u32,
+    // In microseconds since the start of the run
+    start: f64,
+    duration: f64,
+}
+
+impl Trace {
+    pub(crate) fn new(path: PathBuf) -> Trace {
+        Trace {
+            path,
+            start: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    pub(crate) fn cpu(&mut self, name: &'static str, start: Instant, duration: Duration) {
+        let start = micros(start.saturating_duration_since(self.start));
+        self.push(name, CPU, start, duration);
+    }
+
+    // The GPU doesn't say when it ran a query, only how long it took, so the
+    // GPU scopes are laid out one after the other from the moment the frame
+    // was submitted
+    pub(crate) fn gpu(
+        &mut self,
+        name: &'static str,
+        start: Instant,
+        offset: Duration,
+        duration: Duration,
+    ) {
+        let start = micros(start.saturating_duration_since(self.start) + offset);
+        self.push(name, GPU, start, duration);
+    }
+
+    fn push(&mut self, name: &'static str, thread: u32, start: f64, duration: Duration) {
+        self.events.push(Event {
+            name,
+            thread,
+            start,
+            duration: micros(duration),
+        });
+    }
+
+    pub(crate) fn write(&self) {
+        // Names for the two threads, then one complete ("X") event per scope
+        let threads = [(CPU, "CPU"), (GPU, "GPU")].map(|(thread, name)| {
+            format!(
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+                thread, name
+            )
+        });
+
+        let events = self.events.iter().map(|event| {
+            format!(
+                "{{\"name\":{},\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+                json_string(event.name),
+                event.thread,
+                event.start,
+                event.duration
+            )
+        });
+
+        let entries: Vec<String> = threads.into_iter().chain(events).collect();
+        let json = format!(
+            "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+            entries.join(",\n")
+        );
+
+        match std::fs::write(&self.path, json) {
+            Ok(()) => info!("Saved trace to {}", self.path.display()),
+            Err(error) => error!("Could not write {}: {}", self.path.display(), error),
+        }
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+// A quoted JSON string. Debug formatting isn't the same, it writes escapes
+// like \u{1b} that JSON doesn't have.
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if character.is_control() => {
+                json.push_str(&format!("\\u{:04x}", character as u32));
+            }
+            character => json.push(character),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_escaped_for_json() {
+        assert_eq!(json_string("render"), "\"render\"");
+        assert_eq!(json_string("a \"b\" \\ c"), "\"a \\\"b\\\" \\\\ c\"");
+        assert_eq!(json_string("tab\tnew\nline"), "\"tab\\tnew\\nline\"");
+        assert_eq!(json_string("\u{1b}it's"), "\"\\u001bit's\"");
+        assert_eq!(json_string("caf\u{e9}"), "\"caf\u{e9}\"");
+    }
+}
@@ -0,0 +1,96 @@
+use gl::types::{GLenum, GLint, GLuint};
+use nalgebra_glm as glm;
+
+use crate::buffer::Pod;
+
+// The layout of a vertex struct, normally written by #[derive(Vertex)]
+//
+//     #[derive(Clone, Copy, Vertex)]
+//     #[repr(C)]
+//     struct TexturedVertex {
+//         position: [f32; 3],
+//         tex_coords: [f32; 2],
+//     }
+//
+//     let vbo = Buffer::vertices(&vertices, Usage::Static);
+//     let vao = VertexArray::with_layout(&vbo);
+pub trait Vertex: Pod {
+    const ATTRIBUTES: &'static [Attribute];
+}
+
+// One field of a vertex, what glVertexAttribPointer needs apart from the
+// stride, which is the size of the whole vertex
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: &'static str,
+    pub location: GLuint,
+    pub components: GLint,
+    pub component_type: GLenum,
+    pub normalized: bool,
+    // Bytes from the start of the vertex
+    pub offset: usize,
+}
+
+// Field types a vertex can have, a scalar or 1 to 4 of them
+pub trait Component {
+    const COUNT: GLint;
+    const GL_TYPE: GLenum;
+}
+
+// What each component of a field is
+pub trait ComponentType: Copy {
+    const GL_TYPE: GLenum;
+}
+
+impl ComponentType for f32 {
+    const GL_TYPE: GLenum = gl::FLOAT;
+}
+
+impl ComponentType for i8 {
+    const GL_TYPE: GLenum = gl::BYTE;
+}
+
+impl ComponentType for u8 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_BYTE;
+}
+
+impl ComponentType for i16 {
+    const GL_TYPE: GLenum = gl::SHORT;
+}
+
+impl ComponentType for u16 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_SHORT;
+}
+
+impl ComponentType for i32 {
+    const GL_TYPE: GLenum = gl::INT;
+}
+
+impl ComponentType for u32 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_INT;
+}
+
+impl<T: ComponentType> Component for T {
+    const COUNT: GLint = 1;
+    const GL_TYPE: GLenum = T::GL_TYPE;
+}
+
+impl<T: ComponentType, const N: usize> Component for [T; N] {
+    const COUNT: GLint = components(N);
+    const GL_TYPE: GLenum = T::GL_TYPE;
+}
+
+// glm::Vec2, Vec3 and Vec4 are laid out like arrays
+impl<T: ComponentType + glm::Scalar, const N: usize> Component for glm::TVec<T, N> {
+    const COUNT: GLint = components(N);
+    const GL_TYPE: GLenum = T::GL_TYPE;
+}
+
+// An attribute is at most a vec4, a mat4 is four attributes
+const fn components(count: usize) -> GLint {
+    assert!(
+        count >= 1 && count <= 4,
+        "A vertex attribute has 1 to 4 components"
+    );
+    count as GLint
+}
@@ -0,0 +1,182 @@
+use std::mem::size_of;
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use log::warn;
+
+use crate::buffer::{Buffer, ElementBuffer, Index, Pod};
+use crate::context::{self, ContextId};
+use crate::debug;
+use crate::gl_check;
+use crate::vertex::Vertex;
+
+// A vertex array object, deleted when dropped. It remembers which buffers
+// feed which attributes and the element buffer, so drawing is one bind.
+//
+//     let vao = VertexArray::new();
+//     vao.attribute(&vbo, 0, 3, 5, 0);
+//     vao.attribute(&vbo, 1, 2, 5, 3);
+//     vao.draw_arrays(gl::TRIANGLES, 0, 36);
+pub struct VertexArray {
+    id: GLuint,
+    // Index count and type of the attached element buffer
+    elements: Option<(usize, GLenum)>,
+    context: ContextId,
+}
+
+impl VertexArray {
+    pub fn new() -> VertexArray {
+        let context = context::expect_current("vertex array");
+        let mut id = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut id);
+            gl::BindVertexArray(id);
+        }
+
+        VertexArray {
+            id,
+            elements: None,
+            context,
+        }
+    }
+
+    // A vertex array reading every attribute of V from the buffer, laid out
+    // the way #[derive(Vertex)] found them in the struct
+    pub fn with_layout<V: Vertex>(buffer: &Buffer<V>) -> VertexArray {
+        let vao = VertexArray::new();
+
+        for attribute in V::ATTRIBUTES {
+            vao.attribute_bytes(
+                buffer,
+                attribute.location,
+                attribute.components,
+                attribute.component_type,
+                attribute.normalized,
+                size_of::<V>(),
+                attribute.offset,
+            );
+        }
+
+        vao
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        unsafe { gl::BindVertexArray(self.id) };
+    }
+
+    pub fn unbind() {
+        unsafe { gl::BindVertexArray(0) };
+    }
+
+    pub fn label(&self, name: &str) {
+        debug::label(gl::VERTEX_ARRAY, self.id, name);
+    }
+
+    // A float attribute read from a buffer of floats. The stride and offset
+    // are counted in floats like the tutorial's "5 * sizeof(float)", the
+    // byte sizes are worked out here.
+    pub fn attribute(
+        &self,
+        buffer: &Buffer<f32>,
+        location: GLuint,
+        components: GLint,
+        stride: usize,
+        offset: usize,
+    ) {
+        let float = size_of::<f32>();
+
+        self.attribute_bytes(
+            buffer,
+            location,
+            components,
+            gl::FLOAT,
+            false,
+            stride * float,
+            offset * float,
+        );
+    }
+
+    // Any attribute of any buffer with the stride and offset in bytes.
+    // Integer types with normalized false are still read as floats, like
+    // glVertexAttribPointer does.
+    #[allow(clippy::too_many_arguments)]
+    pub fn attribute_bytes<T: Pod>(
+        &self,
+        buffer: &Buffer<T>,
+        location: GLuint,
+        components: GLint,
+        component_type: GLenum,
+        normalized: bool,
+        stride: usize,
+        offset: usize,
+    ) {
+        self.bind();
+        buffer.bind();
+
+        unsafe {
+            gl_check!(gl::VertexAttribPointer(
+                location,
+                components,
+                component_type,
+                if normalized { gl::TRUE } else { gl::FALSE },
+                stride as GLsizei,
+                offset as *const _,
+            ));
+            gl::EnableVertexAttribArray(location);
+        }
+    }
+
+    // The vertex array keeps the element buffer bound, it has to stay alive
+    // as long as the vertex array draws with it. Attach it again after
+    // set_data changes how many indices there are.
+    pub fn set_elements<I: Index>(&mut self, elements: &ElementBuffer<I>) {
+        self.bind();
+        elements.bind();
+        self.elements = Some((elements.len(), elements.index_type()));
+    }
+
+    pub fn draw_arrays(&self, mode: GLenum, first: usize, count: usize) {
+        self.bind();
+        unsafe { gl_check!(gl::DrawArrays(mode, first as GLint, count as GLsizei)) };
+    }
+
+    // Draws every index of the attached element buffer
+    pub fn draw_elements(&self, mode: GLenum) {
+        let Some((count, index_type)) = self.elements else {
+            panic!("Vertex array {} has no element buffer to draw", self.id);
+        };
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::DrawElements(
+                mode,
+                count as GLsizei,
+                index_type,
+                std::ptr::null()
+            ))
+        };
+    }
+}
+
+impl Default for VertexArray {
+    fn default() -> VertexArray {
+        VertexArray::new()
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        if context::current() == Some(self.context) {
+            unsafe { gl::DeleteVertexArrays(1, &self.id) };
+        } else {
+            warn!(
+                "Leaking vertex array {}, its OpenGL context is not current",
+                self.id
+            );
+        }
+    }
+}
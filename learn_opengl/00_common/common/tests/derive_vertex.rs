@@ -0,0 +1,72 @@
+// #[derive(Vertex)] writes ::common paths, so it can only be tried out from
+// outside the crate like a chapter would use it
+use std::mem::size_of;
+
+use common::vertex::Attribute;
+use common::Vertex;
+use nalgebra_glm as glm;
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct MixedVertex {
+    position: [f32; 3],
+    tex_coords: glm::Vec2,
+    #[vertex(normalized)]
+    color: [u8; 4],
+}
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct PlacedVertex {
+    position: [f32; 3],
+    #[vertex(location = 4)]
+    normal: [f32; 3],
+    tex_coords: [f32; 2],
+}
+
+#[test]
+fn mixed_fields() {
+    assert_eq!(
+        MixedVertex::ATTRIBUTES,
+        &[
+            Attribute {
+                name: "position",
+                location: 0,
+                components: 3,
+                component_type: gl::FLOAT,
+                normalized: false,
+                offset: 0,
+            },
+            Attribute {
+                name: "tex_coords",
+                location: 1,
+                components: 2,
+                component_type: gl::FLOAT,
+                normalized: false,
+                offset: 12,
+            },
+            Attribute {
+                name: "color",
+                location: 2,
+                components: 4,
+                component_type: gl::UNSIGNED_BYTE,
+                normalized: true,
+                offset: 20,
+            },
+        ]
+    );
+
+    // The stride VertexArray::with_layout passes
+    assert_eq!(size_of::<MixedVertex>(), 24);
+}
+
+#[test]
+fn locations_carry_on_after_a_placed_one() {
+    let locations: Vec<_> = PlacedVertex::ATTRIBUTES
+        .iter()
+        .map(|attribute| (attribute.location, attribute.offset))
+        .collect();
+
+    assert_eq!(locations, [(0, 0), (4, 12), (5, 24)]);
+    assert_eq!(size_of::<PlacedVertex>(), 32);
+}
@@ -0,0 +1,170 @@
+// Compares rendered frames against reference images for the golden tests.
+// Pixels are compared in YIQ space like pixelmatch does, which is closer to
+// what the eye notices than comparing the RGB values directly.
+
+use image::{Rgba, RgbaImage};
+
+// Largest possible YIQ delta between two colors, the same as pixelmatch's
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    // How different two pixels can be before they count as a mismatch, 0 to 1
+    pub threshold: f32,
+    // Fraction of the pixels that can mismatch, rasterizers disagree on edges
+    pub max_mismatched: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Tolerance {
+        Tolerance {
+            threshold: 0.1,
+            max_mismatched: 0.005,
+        }
+    }
+}
+
+pub struct Comparison {
+    pub mismatched: usize,
+    pub total: usize,
+    // The reference faded out with the mismatched pixels in red
+    pub diff: RgbaImage,
+}
+
+impl Comparison {
+    pub fn mismatched_fraction(&self) -> f32 {
+        self.mismatched as f32 / self.total as f32
+    }
+
+    pub fn passes(&self, tolerance: &Tolerance) -> bool {
+        self.mismatched_fraction() <= tolerance.max_mismatched
+    }
+}
+
+pub fn compare(
+    expected: &RgbaImage,
+    actual: &RgbaImage,
+    tolerance: &Tolerance,
+) -> Result<Comparison, String> {
+    if expected.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "Expected a {}x{} image but got {}x{}",
+            expected.width(),
+            expected.height(),
+            actual.width(),
+            actual.height()
+        ));
+    }
+
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut mismatched = 0;
+
+    for (x, y, expected_pixel) in expected.enumerate_pixels() {
+        let actual_pixel = actual.get_pixel(x, y);
+
+        if color_delta(expected_pixel, actual_pixel) > tolerance.threshold {
+            mismatched += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            let gray = (255.0 - (255.0 - luma(expected_pixel)) * 0.1) as u8;
+            diff.put_pixel(x, y, Rgba([gray, gray, gray, 255]));
+        }
+    }
+
+    Ok(Comparison {
+        mismatched,
+        total: (expected.width() * expected.height()) as usize,
+        diff,
+    })
+}
+
+// 0 for the same color up to 1 for the two most different ones
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let (ay, ai, aq) = yiq(a);
+    let (by, bi, bq) = yiq(b);
+
+    let y = ay - by;
+    let i = ai - bi;
+    let q = aq - bq;
+
+    let delta = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+
+    (delta / MAX_YIQ_DELTA).sqrt()
+}
+
+fn yiq(pixel: &Rgba<u8>) -> (f32, f32, f32) {
+    let [r, g, b] = blend_with_white(pixel);
+
+    (
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_2,
+        r * 0.595_978 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_2 - g * 0.522_617_1 + b * 0.311_146_9,
+    )
+}
+
+fn luma(pixel: &Rgba<u8>) -> f32 {
+    yiq(pixel).0
+}
+
+// Transparent pixels are compared as if they were drawn on white
+fn blend_with_white(pixel: &Rgba<u8>) -> [f32; 3] {
+    let alpha = pixel[3] as f32 / 255.0;
+
+    [0, 1, 2].map(|channel| 255.0 + (pixel[channel] as f32 - 255.0) * alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(color))
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let image = filled(8, 8, [40, 80, 120, 255]);
+        let tolerance = Tolerance::default();
+
+        let comparison = compare(&image, &image, &tolerance).unwrap();
+
+        assert_eq!(comparison.mismatched, 0);
+        assert_eq!(comparison.total, 64);
+        assert!(comparison.passes(&tolerance));
+    }
+
+    #[test]
+    fn small_differences_are_within_tolerance() {
+        let expected = filled(10, 10, [40, 80, 120, 255]);
+        let mut actual = filled(10, 10, [42, 81, 119, 255]);
+        // One pixel that is really off is within the mismatch budget
+        actual.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+        let tolerance = Tolerance {
+            threshold: 0.1,
+            max_mismatched: 0.01,
+        };
+
+        let comparison = compare(&expected, &actual, &tolerance).unwrap();
+
+        assert_eq!(comparison.mismatched, 1);
+        assert_eq!(comparison.diff.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert!(comparison.passes(&tolerance));
+        assert!(!comparison.passes(&Tolerance {
+            max_mismatched: 0.0,
+            ..tolerance
+        }));
+    }
+
+    #[test]
+    fn different_sizes_are_an_error() {
+        let error = compare(
+            &filled(4, 4, [0, 0, 0, 255]),
+            &filled(4, 3, [0, 0, 0, 255]),
+            &Tolerance::default(),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(error, "Expected a 4x4 image but got 4x3");
+    }
+}
@@ -0,0 +1,186 @@
+// Renders every chapter headlessly and compares the last frame against the
+// references in screenshots/golden. Cargo doesn't build other packages'
+// binaries for a test, so run `cargo build --workspace` first. Chapters
+// without a binary are skipped and listed, they don't fail the test.
+//
+// The references are separate from the screenshots in the README. Those are
+// 1824x1480 window captures from a hardware driver at no particular frame,
+// these are rendered by the harness at a fixed size and frame so they can
+// be reproduced anywhere.
+//
+// Set GOLDEN_UPDATE=1 to write the rendered frames as the new references.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use golden::{compare, Tolerance};
+
+// One second into every chapter at a small size keeps the references light
+const FRAMES: &str = "30";
+const FPS: &str = "30";
+const SIZE: &str = "400x300";
+
+struct Chapter {
+    directory: PathBuf,
+    name: String,
+}
+
+impl Chapter {
+    // 13_textures and so on, the same names the screenshots use
+    fn id(&self) -> String {
+        self.directory
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../..")
+        .canonicalize()
+        .unwrap()
+}
+
+fn chapters() -> Vec<Chapter> {
+    let getting_started = workspace_root().join("learn_opengl/01_getting_started");
+
+    let mut chapters: Vec<Chapter> = std::fs::read_dir(getting_started)
+        .unwrap()
+        .filter_map(|entry| {
+            let directory = entry.ok()?.path();
+            let manifest = std::fs::read_to_string(directory.join("Cargo.toml")).ok()?;
+            let name = manifest
+                .lines()
+                .find_map(|line| line.strip_prefix("name = "))?
+                .trim_matches('"')
+                .to_string();
+
+            Some(Chapter { directory, name })
+        })
+        .collect();
+
+    chapters.sort_by_key(|chapter| chapter.id());
+    chapters
+}
+
+// The chapter binaries sit next to the deps folder this test runs from
+fn binary(name: &str) -> PathBuf {
+    let deps = std::env::current_exe().unwrap();
+    let profile = deps.parent().unwrap().parent().unwrap();
+
+    profile.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX))
+}
+
+fn render(chapter: &Chapter, output: &Path) -> Result<image::RgbaImage, String> {
+    let binary = binary(&chapter.name);
+
+    // Chapters load their assets relative to their own folder
+    let result = Command::new(&binary)
+        .current_dir(&chapter.directory)
+        .args([
+            "--headless",
+            "--frames",
+            FRAMES,
+            "--fps",
+            FPS,
+            "--size",
+            SIZE,
+        ])
+        .arg("--output")
+        .arg(output)
+        .output()
+        .map_err(|error| format!("Could not run {}: {}", binary.display(), error))?;
+
+    if !result.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            chapter.name,
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+
+    image::open(output)
+        .map(|image| image.to_rgba8())
+        .map_err(|error| format!("Could not open {}: {}", output.display(), error))
+}
+
+fn check(chapter: &Chapter, output_dir: &Path, update: bool) -> Result<(), String> {
+    let reference = workspace_root()
+        .join("screenshots/golden")
+        .join(format!("{}.png", chapter.id()));
+    let actual_path = output_dir.join(format!("{}.png", chapter.id()));
+
+    let actual = render(chapter, &actual_path)?;
+
+    if update {
+        std::fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        std::fs::copy(&actual_path, &reference).unwrap();
+        return Ok(());
+    }
+
+    let expected = image::open(&reference)
+        .map_err(|error| {
+            format!(
+                "No reference at {} ({}), run with GOLDEN_UPDATE=1 to create it",
+                reference.display(),
+                error
+            )
+        })?
+        .to_rgba8();
+
+    let tolerance = Tolerance::default();
+    let comparison = compare(&expected, &actual, &tolerance)?;
+
+    if comparison.passes(&tolerance) {
+        return Ok(());
+    }
+
+    let diff_path = output_dir.join(format!("{}-diff.png", chapter.id()));
+    comparison.diff.save(&diff_path).unwrap();
+
+    Err(format!(
+        "{:.2}% of the pixels differ, see {} and {}",
+        comparison.mismatched_fraction() * 100.0,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+#[test]
+fn chapters_match_golden_images() {
+    let update = std::env::var_os("GOLDEN_UPDATE").is_some_and(|value| value != "0");
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let (chapters, missing): (Vec<Chapter>, Vec<Chapter>) = chapters()
+        .into_iter()
+        .partition(|chapter| binary(&chapter.name).exists());
+
+    if !missing.is_empty() {
+        let names: Vec<String> = missing.iter().map(|chapter| chapter.id()).collect();
+        eprintln!(
+            "Skipping {} chapters that aren't built, run cargo build --workspace first: {}",
+            missing.len(),
+            names.join(", ")
+        );
+    }
+
+    let failures: Vec<String> = chapters
+        .iter()
+        .filter_map(|chapter| {
+            check(chapter, &output_dir, update)
+                .err()
+                .map(|error| format!("{}: {}", chapter.id(), error))
+        })
+        .collect();
+
+    if !failures.is_empty() {
+        panic!(
+            "{} chapters don't match their golden image\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+}
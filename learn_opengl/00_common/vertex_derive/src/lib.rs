@@ -0,0 +1,164 @@
+// #[derive(Vertex)] for common::Vertex, so a vertex struct describes its own
+// attributes instead of counting floats for VertexAttribPointer.
+//
+//     #[derive(Clone, Copy, Vertex)]
+//     #[repr(C)]
+//     struct ColoredVertex {
+//         position: [f32; 3],
+//         #[vertex(normalized)]
+//         color: [u8; 4],
+//         #[vertex(location = 4)]
+//         tex_coords: [f32; 2],
+//     }
+//
+// Fields get locations 0, 1, 2... in order, a location = n picks one and the
+// fields after it carry on from there, but two fields can't share one.
+// Normalized integers are read as 0 to 1 (or -1 to 1) in the shader.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitInt};
+
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    check_repr_c(&input)?;
+
+    // The padding check below needs the size of every field, which a type
+    // parameter doesn't have until the struct is used
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Vertex can't be derived for a generic struct, its padding can't be checked",
+        ));
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "Vertex can only be derived for structs",
+        ));
+    };
+
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            Span::call_site(),
+            "Vertex needs a struct with named fields",
+        ));
+    };
+
+    let mut next_location = 0u32;
+    let mut attributes = Vec::new();
+    let mut used_locations = Vec::new();
+
+    for field in &fields.named {
+        let mut location = next_location;
+        let mut normalized = false;
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("vertex"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("location") {
+                    location = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("normalized") {
+                    normalized = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `location = n` or `normalized`"))
+                }
+            })?;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        let name = ident.to_string();
+
+        // Both would be fed from the same glVertexAttribPointer and one of
+        // them silently lost
+        if let Some((_, other)) = used_locations.iter().find(|(used, _)| *used == location) {
+            return Err(Error::new_spanned(
+                ident,
+                format!("location {} is already used by {}", location, other),
+            ));
+        }
+        used_locations.push((location, ident.clone()));
+        let ty = &field.ty;
+
+        attributes.push(quote! {
+            ::common::vertex::Attribute {
+                name: #name,
+                location: #location,
+                components: <#ty as ::common::vertex::Component>::COUNT,
+                component_type: <#ty as ::common::vertex::Component>::GL_TYPE,
+                normalized: #normalized,
+                offset: ::core::mem::offset_of!(Self, #ident),
+            }
+        });
+
+        next_location = location + 1;
+    }
+
+    let ident = &input.ident;
+    let types: Vec<&syn::Type> = fields.named.iter().map(|field| &field.ty).collect();
+
+    // Padding bytes are uninitialized, which Pod rules out
+    let message = format!(
+        "{} has padding between its fields, reorder them or add the padding as a field",
+        ident
+    );
+
+    Ok(quote! {
+        impl ::common::vertex::Vertex for #ident {
+            const ATTRIBUTES: &'static [::common::vertex::Attribute] = &[#(#attributes),*];
+        }
+
+        // Every field is Pod and, with the check below, there is no padding
+        unsafe impl ::common::buffer::Pod for #ident where #(#types: ::common::buffer::Pod),* {}
+
+        const _: () = ::core::assert!(
+            ::core::mem::size_of::<#ident>() == 0 #(+ ::core::mem::size_of::<#types>())*,
+            #message
+        );
+    })
+}
+
+// Without repr(C) Rust is free to reorder the fields, and the offsets the
+// shader reads from would not match the struct
+fn check_repr_c(input: &DeriveInput) -> syn::Result<()> {
+    let mut repr_c = false;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                // align(n) and packed(n), which don't matter here
+                let _ = meta.input.parse::<proc_macro2::Group>()?;
+            }
+            Ok(())
+        })?;
+    }
+
+    if repr_c {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            &input.ident,
+            "Vertex needs #[repr(C)] so the fields stay in the order they are written",
+        ))
+    }
+}
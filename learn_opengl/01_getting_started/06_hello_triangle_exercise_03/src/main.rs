@@ -1,16 +1,11 @@
 //Create two shader programs where the second program uses a different fragment shader that outputs the color yellow;
 // draw both triangles again where one outputs the color yellow
 
-use gl;
-use glfw;
-use glfw::Context;
-
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
+use common::{gl_check, label, App, Context};
 
 const VERT_SHADER_SOURCE: &str = "#version 330 core
     layout (location = 0) in vec3 aPos;
-     
+
     void main()
     {
         gl_Position = vec4(aPos.x, aPos.y, aPos.z, 1.0);
@@ -32,314 +27,275 @@ const YELLOW_FRAG_SHADER_SOURCE: &str = "#version 330 core
         FragColor = vec4(1.0f, 1.0f, 0.0f, 1.0f);
     }";
 
-pub struct State {
-    pub wireframe: bool,
+struct HelloTriangleExercise03 {
+    shader_program: u32,
+    yellow_shader_program: u32,
+    vao: u32,
+    vbo: u32,
+    triangle_two_vao: u32,
+    triangle_two_vbo: u32,
 }
 
-fn main() {
-    use glfw::fail_on_errors;
-    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-    glfw.window_hint(glfw::WindowHint::Resizable(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .unwrap();
-
-    window.make_current();
-    window.set_key_polling(true);
-    gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
-
-    window.set_framebuffer_size_callback(glfw_framebuffer_size_callback);
-
-    let mut state = State { wireframe: false };
-
-    state.wireframe = false;
-
-    let vertex_shader = unsafe { gl::CreateShader(gl::VERTEX_SHADER) };
-    unsafe {
-        gl::ShaderSource(
-            vertex_shader,
-            1,
-            &VERT_SHADER_SOURCE.as_bytes().as_ptr().cast(),
-            &VERT_SHADER_SOURCE.len().try_into().unwrap(),
-        );
-        gl::CompileShader(vertex_shader);
-
-        let mut success = 0;
-        gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
-        if success == 0 {
-            let mut log_len = 0_i32;
-            let mut v: Vec<u8> = Vec::with_capacity(512);
-            gl::GetShaderInfoLog(vertex_shader, 512, &mut log_len, v.as_mut_ptr().cast());
-            v.set_len(log_len.try_into().unwrap());
-            panic!(
-                "ERROR::SHADER::VERTEX::COMPILATION_FAILED: {}",
-                String::from_utf8_lossy(&v)
+impl App for HelloTriangleExercise03 {
+    fn init(_ctx: &mut Context) -> Self {
+        let vertex_shader = unsafe { gl::CreateShader(gl::VERTEX_SHADER) };
+        label(gl::SHADER, vertex_shader, "vertex_shader");
+        unsafe {
+            gl::ShaderSource(
+                vertex_shader,
+                1,
+                &VERT_SHADER_SOURCE.as_bytes().as_ptr().cast(),
+                &VERT_SHADER_SOURCE.len().try_into().unwrap(),
             );
+            gl_check!(gl::CompileShader(vertex_shader));
+
+            let mut success = 0;
+            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
+            if success == 0 {
+                let mut log_len = 0_i32;
+                let mut v: Vec<u8> = Vec::with_capacity(512);
+                gl::GetShaderInfoLog(vertex_shader, 512, &mut log_len, v.as_mut_ptr().cast());
+                v.set_len(log_len.try_into().unwrap());
+                panic!(
+                    "ERROR::SHADER::VERTEX::COMPILATION_FAILED: {}",
+                    String::from_utf8_lossy(&v)
+                );
+            }
         }
-    }
 
-    let fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
-    unsafe {
-        gl::ShaderSource(
-            fragment_shader,
-            1,
-            &FRAG_SHADER_SOURCE.as_bytes().as_ptr().cast(),
-            &FRAG_SHADER_SOURCE.len().try_into().unwrap(),
-        );
-        gl::CompileShader(fragment_shader);
-
-        let mut success = 0;
-        gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
-        if success == 0 {
-            let mut v: Vec<u8> = Vec::with_capacity(512);
-            let mut log_len = 0_i32;
-            gl::GetShaderInfoLog(fragment_shader, 512, &mut log_len, v.as_mut_ptr().cast());
-            v.set_len(log_len.try_into().unwrap());
-            panic!(
-                "ERROR::SHADER::FRAGMENT::COMPILATION_FAILED: {}",
-                String::from_utf8_lossy(&v)
+        let fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
+        label(gl::SHADER, fragment_shader, "fragment_shader");
+        unsafe {
+            gl::ShaderSource(
+                fragment_shader,
+                1,
+                &FRAG_SHADER_SOURCE.as_bytes().as_ptr().cast(),
+                &FRAG_SHADER_SOURCE.len().try_into().unwrap(),
             );
+            gl_check!(gl::CompileShader(fragment_shader));
+
+            let mut success = 0;
+            gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
+            if success == 0 {
+                let mut v: Vec<u8> = Vec::with_capacity(512);
+                let mut log_len = 0_i32;
+                gl::GetShaderInfoLog(fragment_shader, 512, &mut log_len, v.as_mut_ptr().cast());
+                v.set_len(log_len.try_into().unwrap());
+                panic!(
+                    "ERROR::SHADER::FRAGMENT::COMPILATION_FAILED: {}",
+                    String::from_utf8_lossy(&v)
+                );
+            }
         }
-    }
 
-    let yellow_fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
-    unsafe {
-        gl::ShaderSource(
-            yellow_fragment_shader,
-            1,
-            &YELLOW_FRAG_SHADER_SOURCE.as_bytes().as_ptr().cast(),
-            &YELLOW_FRAG_SHADER_SOURCE.len().try_into().unwrap(),
-        );
-        gl::CompileShader(yellow_fragment_shader);
-
-        let mut success = 0;
-        gl::GetShaderiv(yellow_fragment_shader, gl::COMPILE_STATUS, &mut success);
-        if success == 0 {
-            let mut v: Vec<u8> = Vec::with_capacity(512);
-            let mut log_len = 0_i32;
-            gl::GetShaderInfoLog(
+        let yellow_fragment_shader = unsafe { gl::CreateShader(gl::FRAGMENT_SHADER) };
+        label(gl::SHADER, yellow_fragment_shader, "yellow_fragment_shader");
+        unsafe {
+            gl::ShaderSource(
                 yellow_fragment_shader,
-                512,
-                &mut log_len,
-                v.as_mut_ptr().cast(),
-            );
-            v.set_len(log_len.try_into().unwrap());
-            panic!(
-                "ERROR::SHADER::FRAGMENT::COMPILATION_FAILED: {}",
-                String::from_utf8_lossy(&v)
+                1,
+                &YELLOW_FRAG_SHADER_SOURCE.as_bytes().as_ptr().cast(),
+                &YELLOW_FRAG_SHADER_SOURCE.len().try_into().unwrap(),
             );
+            gl_check!(gl::CompileShader(yellow_fragment_shader));
+
+            let mut success = 0;
+            gl::GetShaderiv(yellow_fragment_shader, gl::COMPILE_STATUS, &mut success);
+            if success == 0 {
+                let mut v: Vec<u8> = Vec::with_capacity(512);
+                let mut log_len = 0_i32;
+                gl::GetShaderInfoLog(
+                    yellow_fragment_shader,
+                    512,
+                    &mut log_len,
+                    v.as_mut_ptr().cast(),
+                );
+                v.set_len(log_len.try_into().unwrap());
+                panic!(
+                    "ERROR::SHADER::FRAGMENT::COMPILATION_FAILED: {}",
+                    String::from_utf8_lossy(&v)
+                );
+            }
         }
-    }
 
-    let shader_program = unsafe { gl::CreateProgram() };
-    unsafe {
-        gl::AttachShader(shader_program, vertex_shader);
-        gl::AttachShader(shader_program, fragment_shader);
-        gl::LinkProgram(shader_program);
-
-        let mut success = 0;
-        gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
-        if success == 0 {
-            let mut v: Vec<u8> = Vec::with_capacity(1024);
-            let mut log_len = 0_i32;
-            gl::GetProgramInfoLog(shader_program, 1024, &mut log_len, v.as_mut_ptr().cast());
-            v.set_len(log_len.try_into().unwrap());
-            panic!(
-                "ERROR::SHADER::PROGRAM::LINKING_FAILED: {}",
-                String::from_utf8_lossy(&v)
-            );
+        let shader_program = unsafe { gl::CreateProgram() };
+        label(gl::PROGRAM, shader_program, "shader_program");
+        unsafe {
+            gl::AttachShader(shader_program, vertex_shader);
+            gl::AttachShader(shader_program, fragment_shader);
+            gl_check!(gl::LinkProgram(shader_program));
+
+            let mut success = 0;
+            gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
+            if success == 0 {
+                let mut v: Vec<u8> = Vec::with_capacity(1024);
+                let mut log_len = 0_i32;
+                gl::GetProgramInfoLog(shader_program, 1024, &mut log_len, v.as_mut_ptr().cast());
+                v.set_len(log_len.try_into().unwrap());
+                panic!(
+                    "ERROR::SHADER::PROGRAM::LINKING_FAILED: {}",
+                    String::from_utf8_lossy(&v)
+                );
+            }
+
+            gl::DetachShader(shader_program, vertex_shader);
+            gl::DetachShader(shader_program, fragment_shader);
+            //gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(fragment_shader);
         }
 
-        gl::DetachShader(shader_program, vertex_shader);
-        gl::DetachShader(shader_program, fragment_shader);
-        //gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(fragment_shader);
-    }
-
-    let yellow_shader_program = unsafe { gl::CreateProgram() };
-    unsafe {
-        gl::AttachShader(yellow_shader_program, vertex_shader);
-        gl::AttachShader(yellow_shader_program, yellow_fragment_shader);
-        gl::LinkProgram(yellow_shader_program);
-
-        let mut success = 0;
-        gl::GetProgramiv(yellow_shader_program, gl::LINK_STATUS, &mut success);
-        if success == 0 {
-            let mut v: Vec<u8> = Vec::with_capacity(1024);
-            let mut log_len = 0_i32;
-            gl::GetProgramInfoLog(
-                yellow_shader_program,
-                1024,
-                &mut log_len,
-                v.as_mut_ptr().cast(),
-            );
-            v.set_len(log_len.try_into().unwrap());
-            panic!(
-                "ERROR::SHADER::PROGRAM::LINKING_FAILED: {}",
-                String::from_utf8_lossy(&v)
-            );
+        let yellow_shader_program = unsafe { gl::CreateProgram() };
+        label(gl::PROGRAM, yellow_shader_program, "yellow_shader_program");
+        unsafe {
+            gl::AttachShader(yellow_shader_program, vertex_shader);
+            gl::AttachShader(yellow_shader_program, yellow_fragment_shader);
+            gl_check!(gl::LinkProgram(yellow_shader_program));
+
+            let mut success = 0;
+            gl::GetProgramiv(yellow_shader_program, gl::LINK_STATUS, &mut success);
+            if success == 0 {
+                let mut v: Vec<u8> = Vec::with_capacity(1024);
+                let mut log_len = 0_i32;
+                gl::GetProgramInfoLog(
+                    yellow_shader_program,
+                    1024,
+                    &mut log_len,
+                    v.as_mut_ptr().cast(),
+                );
+                v.set_len(log_len.try_into().unwrap());
+                panic!(
+                    "ERROR::SHADER::PROGRAM::LINKING_FAILED: {}",
+                    String::from_utf8_lossy(&v)
+                );
+            }
+
+            gl::DetachShader(yellow_shader_program, vertex_shader);
+            gl::DetachShader(yellow_shader_program, fragment_shader);
+            gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(yellow_fragment_shader);
         }
 
-        gl::DetachShader(yellow_shader_program, vertex_shader);
-        gl::DetachShader(yellow_shader_program, fragment_shader);
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(yellow_fragment_shader);
-    }
+        #[rustfmt::skip]
+        let triangle_one: [f32; 9] = [
+           (-0.5/2.0)-0.3, (-0.5/2.0)-0.3, 0.0, // bottom left
+             (0.5/2.0)-0.3, (-0.5/2.0)-0.3, 0.0, // bottom right
+             0.0 -0.3,  (0.5/2.0)-0.3, 0.0,  // top
+        ];
 
-    #[rustfmt::skip]
-    let triangle_one: [f32; 9] = [
-       (-0.5/2.0)-0.3, (-0.5/2.0)-0.3, 0.0, // bottom left  
-         (0.5/2.0)-0.3, (-0.5/2.0)-0.3, 0.0, // bottom right 
-         0.0 -0.3,  (0.5/2.0)-0.3, 0.0,  // top
-    ];
-
-    #[rustfmt::skip]
-    let triangle_two: [f32; 9] = [
-        (-0.8/2.0)+0.3, (0.8/2.0)+0.3, 0.0, // bottom left  
-         (0.8/2.0)+0.3, (0.8/2.0)+0.3, 0.0, // bottom right 
-         0.0+0.3,  (-0.8/2.0)+0.3, 0.0  // top   
-    ];
-
-    let mut vao = 0;
-    let mut vbo = 0;
-
-    let mut triangle_two_vao = 0;
-    let mut triangle_two_vbo = 0;
-
-    // Triangle 1
-    unsafe {
-        gl::GenVertexArrays(1, &mut vao);
-        gl::GenBuffers(1, &mut vbo);
-
-        gl::BindVertexArray(vao);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER,
-            std::mem::size_of_val(&triangle_one) as isize,
-            triangle_one.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
-
-        gl::VertexAttribPointer(
-            0,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            3 * std::mem::size_of::<f32>() as i32,
-            0 as *const _,
-        );
-        gl::EnableVertexAttribArray(0);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-        gl::BindVertexArray(0);
-    }
+        #[rustfmt::skip]
+        let triangle_two: [f32; 9] = [
+            (-0.8/2.0)+0.3, (0.8/2.0)+0.3, 0.0, // bottom left
+             (0.8/2.0)+0.3, (0.8/2.0)+0.3, 0.0, // bottom right
+             0.0+0.3,  (-0.8/2.0)+0.3, 0.0  // top
+        ];
 
-    unsafe {
-        gl::GenVertexArrays(1, &mut triangle_two_vao);
-        gl::GenBuffers(1, &mut triangle_two_vbo);
-
-        gl::BindVertexArray(triangle_two_vao);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, triangle_two_vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER,
-            std::mem::size_of_val(&triangle_two) as isize,
-            triangle_two.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
-
-        gl::VertexAttribPointer(
-            0,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            3 * std::mem::size_of::<f32>() as i32,
-            0 as *const _,
-        );
-        gl::EnableVertexAttribArray(0);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-        gl::BindVertexArray(0);
-    }
+        let mut vao = 0;
+        let mut vbo = 0;
 
-    while !window.should_close() {
-        glfw.poll_events();
-        for (_, event) in glfw::flush_messages(&events) {
-            glfw_handle_event(&mut window, event, &mut state);
-        }
+        let mut triangle_two_vao = 0;
+        let mut triangle_two_vbo = 0;
 
+        // Triangle 1
         unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-
-            gl::UseProgram(shader_program);
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
 
             gl::BindVertexArray(vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            label(gl::VERTEX_ARRAY, vao, "vao");
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            label(gl::BUFFER, vbo, "vbo");
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&triangle_one) as isize,
+                triangle_one.as_ptr().cast(),
+                gl::STATIC_DRAW,
+            );
 
-            gl::UseProgram(yellow_shader_program);
-            gl::BindVertexArray(triangle_two_vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                3 * std::mem::size_of::<f32>() as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
 
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
             gl::BindVertexArray(0);
         }
 
-        window.swap_buffers();
-    }
+        unsafe {
+            gl::GenVertexArrays(1, &mut triangle_two_vao);
+            gl::GenBuffers(1, &mut triangle_two_vbo);
 
-    unsafe {
-        gl::DeleteVertexArrays(1, &vao);
-        gl::DeleteBuffers(1, &vbo);
-        gl::DeleteProgram(shader_program);
-    }
-}
+            gl::BindVertexArray(triangle_two_vao);
+            label(gl::VERTEX_ARRAY, triangle_two_vao, "triangle_two_vao");
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, triangle_two_vbo);
+            label(gl::BUFFER, triangle_two_vbo, "triangle_two_vbo");
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&triangle_two) as isize,
+                triangle_two.as_ptr().cast(),
+                gl::STATIC_DRAW,
+            );
 
-// This would be the process input function in the C++ version
-fn glfw_handle_event(window: &mut glfw::Window, event: glfw::WindowEvent, state: &mut State) {
-    use glfw::Action;
-    use glfw::Key;
-    use glfw::WindowEvent as Event;
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                3 * std::mem::size_of::<f32>() as i32,
+                std::ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
 
-    match event {
-        Event::Key(Key::Escape, _, Action::Press, _) => {
-            window.set_should_close(true);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
         }
-        Event::Key(Key::F, _, Action::Press, _) => {
-            handle_wireframe(state);
+
+        HelloTriangleExercise03 {
+            shader_program,
+            yellow_shader_program,
+            vao,
+            vbo,
+            triangle_two_vao,
+            triangle_two_vbo,
         }
-        _ => {}
     }
-}
 
-fn glfw_framebuffer_size_callback(_window: &mut glfw::Window, width: i32, height: i32) {
-    unsafe {
-        gl::Viewport(0, 0, width, height);
-    }
-}
+    fn render(&mut self, _ctx: &mut Context) {
+        unsafe {
+            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            gl_check!(gl::UseProgram(self.shader_program));
 
-fn handle_wireframe(state: &mut State) {
-    if state.wireframe {
-        state.wireframe = false;
-    } else {
-        state.wireframe = true;
+            gl::BindVertexArray(self.vao);
+            gl_check!(gl::DrawArrays(gl::TRIANGLES, 0, 3));
+
+            gl_check!(gl::UseProgram(self.yellow_shader_program));
+            gl::BindVertexArray(self.triangle_two_vao);
+            gl_check!(gl::DrawArrays(gl::TRIANGLES, 0, 3));
+
+            gl::BindVertexArray(0);
+        }
     }
 
-    if state.wireframe {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
-    } else {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
+    fn shutdown(&mut self, _ctx: &mut Context) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.triangle_two_vao);
+            gl::DeleteBuffers(1, &self.triangle_two_vbo);
+            gl::DeleteProgram(self.shader_program);
+            gl::DeleteProgram(self.yellow_shader_program);
+        }
     }
 }
+
+fn main() {
+    common::run::<HelloTriangleExercise03>("LearnOpenGL");
+}
@@ -1,40 +1,58 @@
-use std::{ffi::CString, fs};
+use std::ffi::CString;
+use std::rc::Rc;
 
-use gl;
+use common::{gl_check, Assets};
 
 pub struct Shader {
     pub id: u32,
 }
 
 impl Shader {
-    pub fn new(vertex_shader_path: &str, fragment_shader_path: &str) -> Shader {
-        let vertex_shader = Self::compile_shader(gl::VERTEX_SHADER, vertex_shader_path);
-        let fragment_shader = Self::compile_shader(gl::FRAGMENT_SHADER, fragment_shader_path);
+    // The paths are found and cached by the assets so the chapter runs from
+    // any folder
+    pub fn new(
+        assets: &mut Assets,
+        vertex_shader_path: &str,
+        fragment_shader_path: &str,
+    ) -> Shader {
+        let vertex_shader = Self::compile_shader(assets, gl::VERTEX_SHADER, vertex_shader_path);
+        let fragment_shader =
+            Self::compile_shader(assets, gl::FRAGMENT_SHADER, fragment_shader_path);
 
         let shader = Self::compile_program(vertex_shader, fragment_shader);
+        common::label(
+            gl::PROGRAM,
+            shader,
+            &format!(
+                "{} + {}",
+                file_name(vertex_shader_path),
+                file_name(fragment_shader_path)
+            ),
+        );
 
         Shader { id: shader }
     }
 
-    fn read_shader_from_file(file_path: &str) -> String {
-        let raw_shader_source = fs::read_to_string(file_path);
-
-        let shader_source = raw_shader_source.unwrap();
-
-        return shader_source;
+    fn read_shader_from_file(assets: &mut Assets, file_path: &str) -> Rc<str> {
+        assets.text(file_path).unwrap()
     }
 
-    fn compile_shader(shader_type: gl::types::GLenum, shader_path: &str) -> u32 {
-        let binding = Self::read_shader_from_file(&shader_path);
-        let shader_source: &str = binding.as_str();
+    fn compile_shader(
+        assets: &mut Assets,
+        shader_type: gl::types::GLenum,
+        shader_path: &str,
+    ) -> u32 {
+        let binding = Self::read_shader_from_file(assets, shader_path);
+        let shader_source: &str = &binding;
 
-        let foo = CString::new(shader_source).unwrap();
+        let source = CString::new(shader_source).unwrap();
 
         let compiled_shader = unsafe { gl::CreateShader(shader_type) };
+        common::label(gl::SHADER, compiled_shader, file_name(shader_path));
         unsafe {
-            gl::ShaderSource(compiled_shader, 1, &foo.as_ptr(), std::ptr::null());
+            gl::ShaderSource(compiled_shader, 1, &source.as_ptr(), std::ptr::null());
 
-            gl::CompileShader(compiled_shader);
+            gl_check!(gl::CompileShader(compiled_shader));
 
             let mut success = 0;
             gl::GetShaderiv(compiled_shader, gl::COMPILE_STATUS, &mut success);
@@ -50,7 +68,7 @@ impl Shader {
                 );
             }
 
-            return compiled_shader;
+            compiled_shader
         }
     }
 
@@ -59,7 +77,7 @@ impl Shader {
         unsafe {
             gl::AttachShader(shader_program, vertex_shader);
             gl::AttachShader(shader_program, fragment_shader);
-            gl::LinkProgram(shader_program);
+            gl_check!(gl::LinkProgram(shader_program));
 
             let mut success = 0;
             gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
@@ -81,11 +99,11 @@ impl Shader {
             gl::DeleteShader(fragment_shader);
         }
 
-        return shader_program;
+        shader_program
     }
 
     pub fn use_program(&mut self) {
-        unsafe { gl::UseProgram(self.id) };
+        unsafe { gl_check!(gl::UseProgram(self.id)) };
     }
 
     pub fn delete_program(&mut self) {
@@ -97,7 +115,7 @@ impl Shader {
             let location_name = CString::new(name.to_string()).unwrap();
             let location_name_ptr = location_name.as_ptr();
             let location = gl::GetUniformLocation(self.id, location_name_ptr);
-            gl::Uniform1i(location, value as i32);
+            gl_check!(gl::Uniform1i(location, value as i32));
         }
     }
 
@@ -106,7 +124,7 @@ impl Shader {
             let location_name = CString::new(name.to_string()).unwrap();
             let location_name_ptr = location_name.as_ptr();
             let location = gl::GetUniformLocation(self.id, location_name_ptr);
-            gl::Uniform1i(location, value);
+            gl_check!(gl::Uniform1i(location, value));
         }
     }
 
@@ -115,7 +133,15 @@ impl Shader {
             let location_name = CString::new(name.to_string()).unwrap();
             let location_name_ptr = location_name.as_ptr();
             let location = gl::GetUniformLocation(self.id, location_name_ptr);
-            gl::Uniform1f(location, value);
+            gl_check!(gl::Uniform1f(location, value));
         }
     }
 }
+
+// "material.fs" for "assets/shaders/material.fs", used to label the shaders
+fn file_name(path: &str) -> &str {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
@@ -1,267 +1,127 @@
-use gl::types::GLint;
-use gl::{self};
-use glfw;
-use glfw::Context;
+use std::rc::Rc;
+
+use common::{
+    App, Buffer, Context, ElementBuffer, Texture2D, TextureOptions, Usage, Vertex, VertexArray,
+};
 use shader::Shader;
 
 pub mod shader;
 
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
-
-pub struct State {
-    pub wireframe: bool,
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct TexturedVertex {
+    position: [f32; 3],
+    color: [f32; 3],
+    tex_coords: [f32; 2],
 }
 
-fn main() {
-    use glfw::fail_on_errors;
-    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-    glfw.window_hint(glfw::WindowHint::Resizable(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .unwrap();
-
-    window.make_current();
-    window.set_key_polling(true);
-    gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
-
-    window.set_framebuffer_size_callback(glfw_framebuffer_size_callback);
-
-    let mut state = State { wireframe: false };
-
-    state.wireframe = false;
-
-    let mut shader = Shader::new("assets/shaders/vertex.vs", "assets/shaders/fragment.fs");
-
-    #[rustfmt::skip]
-    let vertices: [f32 ; 32] = [
-        // positions          // colors           // texture coords
-        0.5,  0.5, 0.0,   1.0, 0.0, 0.0,   1.0, 1.0,   // top right
-        0.5, -0.5, 0.0,   0.0, 1.0, 0.0,   1.0, 0.0,   // bottom right
-        -0.5, -0.5, 0.0,   0.0, 0.0, 1.0,   0.0, 0.0,   // bottom left
-        -0.5,  0.5, 0.0,   1.0, 1.0, 0.0,   0.0, 1.0    // top left 
-    ];
-
-    #[rustfmt::skip]
-    let indices: [u32; 6] = [
-        0, 1, 3, // first Triangle
-        1, 2, 3, // second Triangle
-    ];
-
-    let mut vao = 0;
-    let mut vbo = 0;
-    let mut ebo = 0;
-    let mut texture: u32 = 0;
-    let mut face_texture: u32 = 0;
-
-    unsafe {
-        gl::GenVertexArrays(1, &mut vao);
-        gl::GenBuffers(1, &mut vbo);
-        gl::GenBuffers(1, &mut ebo);
-
-        gl::BindVertexArray(vao);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER,
-            std::mem::size_of_val(&vertices) as isize,
-            vertices.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
-
-        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-        gl::BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            std::mem::size_of_val(&indices) as isize,
-            indices.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
-
-        gl::VertexAttribPointer(
-            0,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            8 * std::mem::size_of::<f32>() as i32,
-            0 as *const _,
-        );
-        gl::EnableVertexAttribArray(0);
-
-        gl::VertexAttribPointer(
-            1,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            8 * std::mem::size_of::<f32>() as i32,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-        gl::EnableVertexAttribArray(1);
-
-        gl::VertexAttribPointer(
-            2,
-            2,
-            gl::FLOAT,
-            gl::FALSE,
-            8 * std::mem::size_of::<f32>() as i32,
-            (6 * std::mem::size_of::<f32>()) as *const _,
-        );
-        gl::EnableVertexAttribArray(2);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-
-        gl::GenTextures(1, &mut texture);
-        gl::BindTexture(gl::TEXTURE_2D, texture);
-
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+const fn vertex(position: [f32; 3], color: [f32; 3], tex_coords: [f32; 2]) -> TexturedVertex {
+    TexturedVertex {
+        position,
+        color,
+        tex_coords,
+    }
+}
 
-        let image_data = image::open("assets/textures/container.jpg").unwrap();
+struct TextureUnits {
+    shader: Shader,
+    vao: VertexArray,
+    // Only kept so they live as long as the vertex array that reads them
+    _vbo: Buffer<TexturedVertex>,
+    _ebo: ElementBuffer,
+    texture: Rc<Texture2D>,
+    face_texture: Rc<Texture2D>,
+}
 
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            image_data.width() as GLint,
-            image_data.height() as GLint,
-            0,
-            gl::RGB,
-            gl::UNSIGNED_BYTE,
-            image_data.as_bytes().as_ptr() as *const _,
+impl App for TextureUnits {
+    fn init(ctx: &mut Context) -> Self {
+        let mut shader = Shader::new(
+            &mut ctx.assets,
+            "assets/shaders/vertex.vs",
+            "assets/shaders/fragment.fs",
         );
 
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+        #[rustfmt::skip]
+        let vertices = [
+            //     positions          colors           texture coords
+            vertex([ 0.5,  0.5, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0]), // top right
+            vertex([ 0.5, -0.5, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0]), // bottom right
+            vertex([-0.5, -0.5, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0]), // bottom left
+            vertex([-0.5,  0.5, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0]), // top left
+        ];
 
-        gl::GenTextures(1, &mut face_texture);
-        gl::BindTexture(gl::TEXTURE_2D, face_texture);
+        #[rustfmt::skip]
+        let indices: [u32; 6] = [
+            0, 1, 3, // first Triangle
+            1, 2, 3, // second Triangle
+        ];
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        let vbo = Buffer::vertices(&vertices, Usage::Static);
+        vbo.label("vbo");
 
-        let mut face_data = image::open("assets/textures/awesomeface.png").unwrap();
-        face_data = face_data.flipv();
+        let mut vao = VertexArray::with_layout(&vbo);
+        vao.label("vao");
 
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            face_data.width() as GLint,
-            face_data.height() as GLint,
-            0,
-            gl::RGBA,
-            gl::UNSIGNED_BYTE,
-            face_data.as_bytes().as_ptr() as *const _,
-        );
-
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+        let ebo = ElementBuffer::new(&indices, Usage::Static);
+        ebo.label("ebo");
+        vao.set_elements(&ebo);
 
         shader.use_program();
 
         shader.set_int("texture1", 0);
         shader.set_int("texture2", 1);
-    }
 
-    while !window.should_close() {
-        glfw.poll_events();
-        for (_, event) in glfw::flush_messages(&events) {
-            glfw_handle_event(&mut window, event, &mut state);
+        // Both are decoded in the background and drawn white until they are
+        // ready. The face has alpha so it goes up as RGBA, and it is flipped
+        // so it isn't upside down.
+        let texture = ctx
+            .assets
+            .texture_async("assets/textures/container.jpg", &Default::default())
+            .unwrap();
+        let face_texture = ctx
+            .assets
+            .texture_async(
+                "assets/textures/awesomeface.png",
+                &TextureOptions {
+                    flip: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        TextureUnits {
+            shader,
+            vao,
+            _vbo: vbo,
+            _ebo: ebo,
+            texture,
+            face_texture,
         }
+    }
 
+    fn render(&mut self, _ctx: &mut Context) {
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, texture);
-
-            gl::ActiveTexture(gl::TEXTURE1);
-            gl::BindTexture(gl::TEXTURE_2D, face_texture);
+            self.texture.bind(0);
+            self.face_texture.bind(1);
 
-            gl::BindVertexArray(vao);
-            shader.use_program();
-            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null());
+            self.shader.use_program();
+            self.vao.draw_elements(gl::TRIANGLES);
 
             gl::BindVertexArray(0);
-
-            gl_last_error();
-        }
-
-        window.swap_buffers();
-    }
-
-    unsafe {
-        gl::DeleteVertexArrays(1, &vao);
-        gl::DeleteBuffers(1, &vbo);
-        shader.delete_program();
-    }
-}
-
-// This would be the process input function in the C++ version
-fn glfw_handle_event(window: &mut glfw::Window, event: glfw::WindowEvent, state: &mut State) {
-    use glfw::Action;
-    use glfw::Key;
-    use glfw::WindowEvent as Event;
-
-    match event {
-        Event::Key(Key::Escape, _, Action::Press, _) => {
-            window.set_should_close(true);
         }
-        Event::Key(Key::F, _, Action::Press, _) => {
-            handle_wireframe(state);
-        }
-        _ => {}
-    }
-}
-
-fn glfw_framebuffer_size_callback(_window: &mut glfw::Window, width: i32, height: i32) {
-    unsafe {
-        gl::Viewport(0, 0, width, height);
-    }
-}
-
-fn handle_wireframe(state: &mut State) {
-    if state.wireframe {
-        state.wireframe = false;
-    } else {
-        state.wireframe = true;
     }
 
-    if state.wireframe {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
-    } else {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
+    fn shutdown(&mut self, _ctx: &mut Context) {
+        self.shader.delete_program();
     }
 }
 
-fn gl_last_error() {
-    unsafe {
-        let errno = gl::GetError();
+fn main() {
+    #[cfg(feature = "embedded-assets")]
+    common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
 
-        if errno != gl::NO_ERROR {
-            println!("Last GL Error = {}", errno);
-        }
-    }
+    common::run::<TextureUnits>("LearnOpenGL");
 }
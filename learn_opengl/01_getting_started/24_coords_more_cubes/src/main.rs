@@ -1,319 +1,180 @@
 extern crate nalgebra_glm as glm;
 
-use gl::types::GLint;
-use gl::{self};
-use glfw;
-use glfw::Context;
+use std::rc::Rc;
+
+use common::{
+    App, Buffer, Context, SceneFile, Texture2D, TextureOptions, Usage, Vertex, VertexArray,
+};
 use shader::Shader;
 
 pub mod shader;
 
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
-
-pub struct State {
-    pub wireframe: bool,
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct TexturedVertex {
+    position: [f32; 3],
+    tex_coords: [f32; 2],
 }
 
-fn main() {
-    use glfw::fail_on_errors;
-    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-    glfw.window_hint(glfw::WindowHint::Resizable(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .unwrap();
-
-    window.make_current();
-    window.set_key_polling(true);
-    gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
-
-    window.set_framebuffer_size_callback(glfw_framebuffer_size_callback);
-
-    let mut state = State { wireframe: false };
-
-    state.wireframe = false;
-
-    let mut shader = Shader::new("assets/shaders/vertex.vs", "assets/shaders/fragment.fs");
-
-    #[rustfmt::skip]
-    let vertices = [
-        -0.5f32, -0.5, -0.5,  0.0, 0.0,
-         0.5, -0.5, -0.5,  1.0, 0.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-        -0.5,  0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 0.0,
-
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 1.0,
-         0.5,  0.5,  0.5,  1.0, 1.0,
-        -0.5,  0.5,  0.5,  0.0, 1.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-
-        -0.5,  0.5,  0.5,  1.0, 0.0,
-        -0.5,  0.5, -0.5,  1.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-        -0.5,  0.5,  0.5,  1.0, 0.0,
-
-         0.5,  0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5,  0.5,  0.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5, -0.5,  1.0, 1.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-
-        -0.5,  0.5, -0.5,  0.0, 1.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-        -0.5,  0.5,  0.5,  0.0, 0.0,
-        -0.5,  0.5, -0.5,  0.0, 1.0
-    ];
-
-    let cube_positions = [
-        glm::vec3(0.0, 0.0, 0.0),
-        glm::vec3(2.0, 5.0, -15.0),
-        glm::vec3(-1.5, -2.2, -2.5),
-        glm::vec3(-3.8, -2.0, -12.3),
-        glm::vec3(2.4, -0.4, -3.5),
-        glm::vec3(-1.7, 3.0, -7.5),
-        glm::vec3(1.3, -2.0, -2.5),
-        glm::vec3(1.5, 2.0, -2.5),
-        glm::vec3(1.5, 0.2, -1.5),
-        glm::vec3(-1.3, 1.0, -1.5),
-    ];
-
-    let mut vao = 0;
-    let mut vbo = 0;
-
-    let mut texture: u32 = 0;
-    let mut face_texture: u32 = 0;
-
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-
-        gl::GenVertexArrays(1, &mut vao);
-        gl::GenBuffers(1, &mut vbo);
-
-        gl::BindVertexArray(vao);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER,
-            std::mem::size_of_val(&vertices) as isize,
-            vertices.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
-
-        gl::VertexAttribPointer(
-            0,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            5 * std::mem::size_of::<f32>() as i32,
-            0 as *const _,
-        );
-        gl::EnableVertexAttribArray(0);
-
-        gl::VertexAttribPointer(
-            1,
-            2,
-            gl::FLOAT,
-            gl::FALSE,
-            5 * std::mem::size_of::<f32>() as i32,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-        gl::EnableVertexAttribArray(1);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+const fn vertex(position: [f32; 3], tex_coords: [f32; 2]) -> TexturedVertex {
+    TexturedVertex {
+        position,
+        tex_coords,
+    }
+}
 
-        gl::GenTextures(1, &mut texture);
-        gl::BindTexture(gl::TEXTURE_2D, texture);
+struct CoordsMoreCubes {
+    shader: Shader,
+    vao: VertexArray,
+    // Only kept so it lives as long as the vertex array that reads it
+    _vbo: Buffer<TexturedVertex>,
+    texture: Rc<Texture2D>,
+    face_texture: Rc<Texture2D>,
+    scene: SceneFile,
+}
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
+impl App for CoordsMoreCubes {
+    fn init(ctx: &mut Context) -> Self {
+        let mut shader = Shader::new(
+            &mut ctx.assets,
+            "assets/shaders/vertex.vs",
+            "assets/shaders/fragment.fs",
         );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-
-        let image_data = image::open("assets/textures/container.jpg").unwrap();
-
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            image_data.width() as GLint,
-            image_data.height() as GLint,
-            0,
-            gl::RGB,
-            gl::UNSIGNED_BYTE,
-            image_data.as_bytes().as_ptr() as *const _,
-        );
-
-        gl::GenerateMipmap(gl::TEXTURE_2D);
 
-        gl::GenTextures(1, &mut face_texture);
-        gl::BindTexture(gl::TEXTURE_2D, face_texture);
+        #[rustfmt::skip]
+        let vertices = [
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+        ];
+
+        let scene = SceneFile::from_assets(&ctx.assets, "assets/scene.ron");
+
+        // The face is flipped so it isn't upside down
+        let texture = ctx
+            .assets
+            .texture("assets/textures/container.jpg", &Default::default())
+            .unwrap();
+        let face_texture = ctx
+            .assets
+            .texture(
+                "assets/textures/awesomeface.png",
+                &TextureOptions {
+                    flip: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let vbo = Buffer::vertices(&vertices, Usage::Static);
+        vbo.label("vbo");
+
+        let vao = VertexArray::with_layout(&vbo);
+        vao.label("vao");
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-
-        let mut face_data = image::open("assets/textures/awesomeface.png").unwrap();
-        face_data = face_data.flipv();
-
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            face_data.width() as GLint,
-            face_data.height() as GLint,
-            0,
-            gl::RGBA,
-            gl::UNSIGNED_BYTE,
-            face_data.as_bytes().as_ptr() as *const _,
-        );
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
 
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+            shader.use_program();
 
-        shader.use_program();
+            shader.set_int("texture1", 0);
+            shader.set_int("texture2", 1);
+        }
 
-        shader.set_int("texture1", 0);
-        shader.set_int("texture2", 1);
+        CoordsMoreCubes {
+            shader,
+            vao,
+            _vbo: vbo,
+            texture,
+            face_texture,
+            scene,
+        }
     }
 
-    while !window.should_close() {
-        glfw.poll_events();
-        for (_, event) in glfw::flush_messages(&events) {
-            glfw_handle_event(&mut window, event, &mut state);
-        }
+    fn update(&mut self, _ctx: &mut Context) {
+        self.scene.reload_if_changed();
+    }
 
+    fn render(&mut self, ctx: &mut Context) {
         unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+            let [red, green, blue, alpha] = self.scene.clear_color;
+            gl::ClearColor(red, green, blue, alpha);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, texture);
-
-            gl::ActiveTexture(gl::TEXTURE1);
-            gl::BindTexture(gl::TEXTURE_2D, face_texture);
+            self.texture.bind(0);
+            self.face_texture.bind(1);
 
             let mut view = glm::Mat4::identity();
-            let projection;
 
             view = glm::translate(&view, &glm::vec3(0.0, 0.0, -3.0));
-            projection = glm::perspective(
-                45.0f32.to_radians(),
-                SCR_WIDTH as f32 / SCR_HEIGHT as f32,
-                0.1,
-                100.0,
-            );
+            let projection = glm::perspective(ctx.aspect_ratio(), 45.0f32.to_radians(), 0.1, 100.0);
 
-            shader.use_program();
-
-            shader.set_mat4("view", view);
-            shader.set_mat4("projection", projection);
+            self.shader.use_program();
 
-            gl::BindVertexArray(vao);
+            self.shader.set_mat4("view", view);
+            self.shader.set_mat4("projection", projection);
 
-            for (i, cube_position) in cube_positions.iter().enumerate() {
+            for object in &self.scene.objects {
+                // Unlike the later chapters this one never converted the
+                // angle to radians, so the cubes are turned a lot further
                 let mut model = glm::Mat4::identity();
-                model = glm::translate(&model, cube_position);
-                let angle: f32 = 20.0 * i as f32;
-                model = glm::rotate(&model, angle, &glm::vec3(1.0, 0.3, 0.5));
-                shader.set_mat4("model", model);
+                model = glm::translate(&model, &object.position());
+                model = glm::rotate(&model, object.angle, &object.axis.into());
+                self.shader.set_mat4("model", model);
 
-                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                self.vao.draw_arrays(gl::TRIANGLES, 0, 36);
             }
 
             gl::BindVertexArray(0);
-
-            gl_last_error();
         }
-
-        window.swap_buffers();
     }
 
-    unsafe {
-        gl::DeleteVertexArrays(1, &vao);
-        gl::DeleteBuffers(1, &vbo);
-        shader.delete_program();
+    fn shutdown(&mut self, _ctx: &mut Context) {
+        self.shader.delete_program();
     }
 }
 
-// This would be the process input function in the C++ version
-fn glfw_handle_event(window: &mut glfw::Window, event: glfw::WindowEvent, state: &mut State) {
-    use glfw::Action;
-    use glfw::Key;
-    use glfw::WindowEvent as Event;
-
-    match event {
-        Event::Key(Key::Escape, _, Action::Press, _) => {
-            window.set_should_close(true);
-        }
-        Event::Key(Key::F, _, Action::Press, _) => {
-            handle_wireframe(state);
-        }
-        _ => {}
-    }
-}
-
-fn glfw_framebuffer_size_callback(_window: &mut glfw::Window, width: i32, height: i32) {
-    unsafe {
-        gl::Viewport(0, 0, width, height);
-    }
-}
-
-fn handle_wireframe(state: &mut State) {
-    if state.wireframe {
-        state.wireframe = false;
-    } else {
-        state.wireframe = true;
-    }
-
-    if state.wireframe {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
-    } else {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
-    }
-}
-
-fn gl_last_error() {
-    unsafe {
-        let errno = gl::GetError();
+fn main() {
+    #[cfg(feature = "embedded-assets")]
+    common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
 
-        if errno != gl::NO_ERROR {
-            println!("Last GL Error = {}", errno);
-        }
-    }
+    common::run::<CoordsMoreCubes>("LearnOpenGL");
 }
@@ -1,385 +1,231 @@
 extern crate nalgebra_glm as glm;
 
-use gl::types::GLint;
-use gl::{self};
-use glfw;
-use glfw::ffi::glfwGetTime;
-use glfw::Context;
+use std::rc::Rc;
+
+use common::scene::CameraStart;
+use common::{
+    App, Buffer, Context, SceneFile, Texture2D, TextureOptions, Usage, Vertex, VertexArray,
+};
+use glfw::Key;
 use shader::Shader;
 
 pub mod shader;
 
-const SCR_WIDTH: u32 = 800;
-const SCR_HEIGHT: u32 = 600;
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct TexturedVertex {
+    position: [f32; 3],
+    tex_coords: [f32; 2],
+}
 
-pub struct State {
-    pub wireframe: bool,
-    pub camera_position: glm::Vec3,
-    pub delta_time: f32,
-    pub last_frame: f32,
+const fn vertex(position: [f32; 3], tex_coords: [f32; 2]) -> TexturedVertex {
+    TexturedVertex {
+        position,
+        tex_coords,
+    }
 }
 
-fn main() {
-    use glfw::fail_on_errors;
-    let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-    glfw.window_hint(glfw::WindowHint::Resizable(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-        glfw::OpenGlProfileHint::Core,
-    ));
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-
-    let (mut window, events) = glfw
-        .create_window(
-            SCR_WIDTH,
-            SCR_HEIGHT,
-            "LearnOpenGL",
-            glfw::WindowMode::Windowed,
-        )
-        .unwrap();
-
-    window.make_current();
-    window.set_key_polling(true);
-    gl::load_with(|ptr| window.get_proc_address(ptr) as *const _);
-
-    window.set_framebuffer_size_callback(glfw_framebuffer_size_callback);
-
-    let mut state = State {
-        wireframe: false,
-        camera_position: glm::vec3(0.0, 0.0, 3.0),
-        delta_time: 0.0,
-        last_frame: 0.0,
-    };
-
-    state.wireframe = false;
-
-    let mut shader = Shader::new("assets/shaders/vertex.vs", "assets/shaders/fragment.fs");
-
-    #[rustfmt::skip]
-    let vertices = [
-        -0.5f32, -0.5, -0.5,  0.0, 0.0,
-         0.5, -0.5, -0.5,  1.0, 0.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-        -0.5,  0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 0.0,
-
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 1.0,
-         0.5,  0.5,  0.5,  1.0, 1.0,
-        -0.5,  0.5,  0.5,  0.0, 1.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-
-        -0.5,  0.5,  0.5,  1.0, 0.0,
-        -0.5,  0.5, -0.5,  1.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-        -0.5,  0.5,  0.5,  1.0, 0.0,
-
-         0.5,  0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5,  0.5,  0.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-         0.5, -0.5, -0.5,  1.0, 1.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-         0.5, -0.5,  0.5,  1.0, 0.0,
-        -0.5, -0.5,  0.5,  0.0, 0.0,
-        -0.5, -0.5, -0.5,  0.0, 1.0,
-
-        -0.5,  0.5, -0.5,  0.0, 1.0,
-         0.5,  0.5, -0.5,  1.0, 1.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-         0.5,  0.5,  0.5,  1.0, 0.0,
-        -0.5,  0.5,  0.5,  0.0, 0.0,
-        -0.5,  0.5, -0.5,  0.0, 1.0
-    ];
-
-    let cube_positions = [
-        glm::vec3(0.0, 0.0, 0.0),
-        glm::vec3(2.0, 5.0, -15.0),
-        glm::vec3(-1.5, -2.2, -2.5),
-        glm::vec3(-3.8, -2.0, -12.3),
-        glm::vec3(2.4, -0.4, -3.5),
-        glm::vec3(-1.7, 3.0, -7.5),
-        glm::vec3(1.3, -2.0, -2.5),
-        glm::vec3(1.5, 2.0, -2.5),
-        glm::vec3(1.5, 0.2, -1.5),
-        glm::vec3(-1.3, 1.0, -1.5),
-    ];
-
-    let mut vao = 0;
-    let mut vbo = 0;
-
-    let mut texture: u32 = 0;
-    let mut face_texture: u32 = 0;
-
-    unsafe {
-        gl::Enable(gl::DEPTH_TEST);
-
-        gl::GenVertexArrays(1, &mut vao);
-        gl::GenBuffers(1, &mut vbo);
-
-        gl::BindVertexArray(vao);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(
-            gl::ARRAY_BUFFER,
-            std::mem::size_of_val(&vertices) as isize,
-            vertices.as_ptr().cast(),
-            gl::STATIC_DRAW,
-        );
+struct CameraWalkAround {
+    shader: Shader,
+    vao: VertexArray,
+    // Only kept so it lives as long as the vertex array that reads it
+    _vbo: Buffer<TexturedVertex>,
+    texture: Rc<Texture2D>,
+    face_texture: Rc<Texture2D>,
+    scene: SceneFile,
+    camera_position: glm::Vec3,
+}
 
-        gl::VertexAttribPointer(
-            0,
-            3,
-            gl::FLOAT,
-            gl::FALSE,
-            5 * std::mem::size_of::<f32>() as i32,
-            0 as *const _,
+impl App for CameraWalkAround {
+    fn init(ctx: &mut Context) -> Self {
+        let mut shader = Shader::new(
+            &mut ctx.assets,
+            "assets/shaders/vertex.vs",
+            "assets/shaders/fragment.fs",
         );
-        gl::EnableVertexAttribArray(0);
-
-        gl::VertexAttribPointer(
-            1,
-            2,
-            gl::FLOAT,
-            gl::FALSE,
-            5 * std::mem::size_of::<f32>() as i32,
-            (3 * std::mem::size_of::<f32>()) as *const _,
-        );
-        gl::EnableVertexAttribArray(1);
-
-        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
 
-        gl::GenTextures(1, &mut texture);
-        gl::BindTexture(gl::TEXTURE_2D, texture);
+        #[rustfmt::skip]
+        let vertices = [
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+        ];
+
+        let scene = SceneFile::from_assets(&ctx.assets, "assets/scene.ron");
+
+        // Picks up where the last run left off
+        let start = ctx.saved_camera().unwrap_or_else(|| scene.camera.clone());
+
+        // The face is flipped so it isn't upside down
+        let texture = ctx
+            .assets
+            .texture("assets/textures/container.jpg", &Default::default())
+            .unwrap();
+        let face_texture = ctx
+            .assets
+            .texture(
+                "assets/textures/awesomeface.png",
+                &TextureOptions {
+                    flip: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let vbo = Buffer::vertices(&vertices, Usage::Static);
+        vbo.label("vbo");
+
+        let vao = VertexArray::with_layout(&vbo);
+        vao.label("vao");
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-
-        let image_data = image::open("assets/textures/container.jpg").unwrap();
-
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            image_data.width() as GLint,
-            image_data.height() as GLint,
-            0,
-            gl::RGB,
-            gl::UNSIGNED_BYTE,
-            image_data.as_bytes().as_ptr() as *const _,
-        );
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
 
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+            shader.use_program();
 
-        gl::GenTextures(1, &mut face_texture);
-        gl::BindTexture(gl::TEXTURE_2D, face_texture);
+            shader.set_int("texture1", 0);
+            shader.set_int("texture2", 1);
+        }
 
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as GLint,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-
-        let mut face_data = image::open("assets/textures/awesomeface.png").unwrap();
-        face_data = face_data.flipv();
-
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGB as GLint,
-            face_data.width() as GLint,
-            face_data.height() as GLint,
-            0,
-            gl::RGBA,
-            gl::UNSIGNED_BYTE,
-            face_data.as_bytes().as_ptr() as *const _,
-        );
+        CameraWalkAround {
+            shader,
+            vao,
+            _vbo: vbo,
+            texture,
+            face_texture,
+            camera_position: start.position.into(),
+            scene,
+        }
+    }
 
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+    // This would be the process input function in the C++ version
+    fn update(&mut self, ctx: &mut Context) {
+        self.scene.reload_if_changed();
 
-        shader.use_program();
+        let camera_front: glm::Vec3 = glm::vec3(0.0, 0.0, -1.0);
+        let camera_up: glm::Vec3 = glm::vec3(0.0, 1.0, 0.0);
 
-        shader.set_int("texture1", 0);
-        shader.set_int("texture2", 1);
-    }
+        let camera_speed: f32 = 2.5 * ctx.time.delta;
 
-    while !window.should_close() {
-        glfw.poll_events();
-        for (_, event) in glfw::flush_messages(&events) {
-            glfw_handle_event(&mut window, event, &mut state);
+        if ctx.input.key_down(Key::W) {
+            self.camera_position += camera_speed * camera_front;
         }
 
-        unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        if ctx.input.key_down(Key::S) {
+            self.camera_position -= camera_speed * camera_front;
+        }
 
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindTexture(gl::TEXTURE_2D, texture);
+        if ctx.input.key_down(Key::A) {
+            self.camera_position -=
+                glm::normalize(&glm::cross(&camera_front, &camera_up)) * camera_speed;
+        }
 
-            gl::ActiveTexture(gl::TEXTURE1);
-            gl::BindTexture(gl::TEXTURE_2D, face_texture);
+        if ctx.input.key_down(Key::D) {
+            self.camera_position +=
+                glm::normalize(&glm::cross(&camera_front, &camera_up)) * camera_speed;
+        }
+    }
 
-            shader.use_program();
+    fn render(&mut self, ctx: &mut Context) {
+        unsafe {
+            let [red, green, blue, alpha] = self.scene.clear_color;
+            gl::ClearColor(red, green, blue, alpha);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
-            let projection;
+            self.texture.bind(0);
+            self.face_texture.bind(1);
 
-            projection = glm::perspective(
-                45.0f32.to_radians(),
-                SCR_WIDTH as f32 / SCR_HEIGHT as f32,
-                0.1,
-                100.0,
-            );
+            self.shader.use_program();
 
-            shader.set_mat4("projection", projection);
+            let projection = glm::perspective(ctx.aspect_ratio(), 45.0f32.to_radians(), 0.1, 100.0);
 
-            let view;
+            self.shader.set_mat4("projection", projection);
 
             // let radius: f32 = 10.0;
-            // let cam_x: f32 = (glfwGetTime() as f32).sin() * radius;
-            // let cam_z: f32 = (glfwGetTime() as f32).cos() * radius;
+            // let cam_x: f32 = ctx.time.elapsed.sin() * radius;
+            // let cam_z: f32 = ctx.time.elapsed.cos() * radius;
 
             let camera_front: glm::Vec3 = glm::vec3(0.0, 0.0, -1.0);
             let camera_up: glm::Vec3 = glm::vec3(0.0, 1.0, 0.0);
 
-            view = glm::look_at(
-                &state.camera_position,
-                &(state.camera_position + camera_front),
+            let view = glm::look_at(
+                &self.camera_position,
+                &(self.camera_position + camera_front),
                 &camera_up,
             );
 
             //view = glm::lookAt(cameraPos, cameraPos + cameraFront, cameraUp);
 
-            shader.set_mat4("view", view);
+            self.shader.set_mat4("view", view);
 
-            gl::BindVertexArray(vao);
-
-            for (i, cube_position) in cube_positions.iter().enumerate() {
-                let mut model = glm::Mat4::identity();
-                model = glm::translate(&model, cube_position);
-
-                let mut angle: f32 = 20.0 * i as f32;
+            for (i, object) in self.scene.objects.iter().enumerate() {
+                let mut object = object.clone();
 
                 if i % 3 == 0 {
-                    angle = 20.0 * glfwGetTime() as f32;
+                    object.angle = 20.0 * ctx.time.elapsed;
                 }
 
-                model = glm::rotate(&model, angle.to_radians(), &glm::vec3(1.0, 0.3, 0.5));
-
-                shader.set_mat4("model", model);
+                self.shader.set_mat4("model", object.model());
 
-                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                self.vao.draw_arrays(gl::TRIANGLES, 0, 36);
             }
 
             gl::BindVertexArray(0);
-
-            gl_last_error();
         }
-
-        window.swap_buffers();
-    }
-
-    unsafe {
-        gl::DeleteVertexArrays(1, &vao);
-        gl::DeleteBuffers(1, &vbo);
-        shader.delete_program();
     }
-}
-
-// This would be the process input function in the C++ version
-fn glfw_handle_event(window: &mut glfw::Window, event: glfw::WindowEvent, state: &mut State) {
-    use glfw::Action;
-    use glfw::Key;
 
-    match event {
-        glfw::WindowEvent::Key(key, _scancode, action, _mods) => match (key, action) {
-            (Key::Escape, Action::Press) => {
-                window.set_should_close(true);
-            }
-            (Key::F, Action::Press) => {
-                handle_wireframe(state);
-            }
-            (Key::W | Key::A | Key::D | Key::S, Action::Press) => {
-                handle_movement(state, key);
-            }
-            _ => {}
-        },
-        _ => {}
-    }
-}
+    fn shutdown(&mut self, ctx: &mut Context) {
+        // The camera only moves, it always looks down -Z
+        ctx.save_camera(CameraStart {
+            position: self.camera_position.into(),
+            ..self.scene.camera.clone()
+        });
 
-fn glfw_framebuffer_size_callback(_window: &mut glfw::Window, width: i32, height: i32) {
-    unsafe {
-        gl::Viewport(0, 0, width, height);
+        self.shader.delete_program();
     }
 }
 
-fn handle_movement(state: &mut State, key: glfw::Key) {
-    let camera_front: glm::Vec3 = glm::vec3(0.0, 0.0, -1.0);
-    let camera_up: glm::Vec3 = glm::vec3(0.0, 1.0, 0.0);
-
-    let current_frame: f32 = unsafe { glfwGetTime() as f32 };
-    state.delta_time = current_frame - state.last_frame;
-    state.last_frame = current_frame;
-
-    let camera_speed: f32 = 2.5 * state.delta_time;
-
-    if key == glfw::Key::W {
-        state.camera_position += camera_speed * camera_front;
-    }
-
-    if key == glfw::Key::S {
-        state.camera_position -= camera_speed * camera_front;
-    }
-
-    if key == glfw::Key::A {
-        state.camera_position -=
-            glm::normalize(&glm::cross(&camera_front, &camera_up)) * camera_speed;
-    }
-
-    if key == glfw::Key::D {
-        state.camera_position +=
-            glm::normalize(&glm::cross(&camera_front, &camera_up)) * camera_speed;
-    }
-}
-
-fn handle_wireframe(state: &mut State) {
-    if state.wireframe {
-        state.wireframe = false;
-    } else {
-        state.wireframe = true;
-    }
-
-    if state.wireframe {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE) };
-    } else {
-        unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL) };
-    }
-}
-
-fn gl_last_error() {
-    unsafe {
-        let errno = gl::GetError();
+fn main() {
+    #[cfg(feature = "embedded-assets")]
+    common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
 
-        if errno != gl::NO_ERROR {
-            println!("Last GL Error = {}", errno);
-        }
-    }
+    common::run::<CameraWalkAround>("LearnOpenGL");
 }
@@ -29,13 +29,13 @@ pub struct Camera {
 impl Camera {
     pub fn new(position: glm::Vec3, up: glm::Vec3, yaw: f32, pitch: f32) -> Camera {
         Camera {
-            position: position,
+            position,
             front: glm::vec3(0.0, 0.0, -0.1),
-            up: up,
+            up,
             right: glm::vec3(0.0, 0.0, 0.0),
             world_up: up,
-            yaw: yaw,
-            pitch: pitch,
+            yaw,
+            pitch,
             movement_speed: SPEED,
             mouse_sensitivity: SENSITIVITY,
             zoom: ZOOM,
@@ -43,9 +43,7 @@ impl Camera {
     }
 
     pub fn get_view_matrix(&mut self) -> glm::Mat4 {
-        let view = glm::look_at(&self.position, &(&self.position + &self.front), &self.up);
-
-        return view;
+        glm::look_at(&self.position, &(self.position + self.front), &self.up)
     }
 
     pub fn process_keyboard(&mut self, direction: CameraMovement, delta_time: f32) {
@@ -77,13 +75,7 @@ impl Camera {
         self.pitch += local_y_offset;
 
         if constrain_pitch {
-            if self.pitch > 89.0 {
-                self.pitch = 89.0
-            }
-
-            if self.pitch < -89.0 {
-                self.pitch = -89.0
-            }
+            self.pitch = self.pitch.clamp(-89.0, 89.0);
         }
 
         self.update_camera_vectors();
@@ -92,13 +84,7 @@ impl Camera {
     pub fn process_mouse_scroll(&mut self, y_offset: f64) {
         self.zoom -= y_offset as f32;
 
-        if self.zoom < 1.0 {
-            self.zoom = 1.0;
-        }
-
-        if self.zoom > 45.0 {
-            self.zoom = 45.0;
-        }
+        self.zoom = self.zoom.clamp(1.0, 45.0);
     }
 
     pub fn update_camera_vectors(&mut self) {
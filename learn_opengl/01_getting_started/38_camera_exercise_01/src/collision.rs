@@ -0,0 +1,155 @@
+extern crate nalgebra_glm as glm;
+
+// Axis aligned bounding box used for the scene cubes and the player body
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: glm::Vec3, max: glm::Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn from_center(center: glm::Vec3, half_extents: glm::Vec3) -> Aabb {
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    pub fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> glm::Vec3 {
+        self.max - self.min
+    }
+
+    // Touching faces do not count so the player can stand on top of a box
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x
+            && self.max.x > other.min.x
+            && self.min.y < other.max.y
+            && self.max.y > other.min.y
+            && self.min.z < other.max.z
+            && self.max.z > other.min.z
+    }
+}
+
+// Anything the player can walk on
+pub trait Ground {
+    fn height_at(&self, x: f32, z: f32) -> f32;
+}
+
+pub struct GroundPlane {
+    pub height: f32,
+}
+
+impl Ground for GroundPlane {
+    fn height_at(&self, _x: f32, _z: f32) -> f32 {
+        self.height
+    }
+}
+
+// Regular grid of heights centered on the origin, sampled bilinearly
+pub struct Heightfield {
+    pub size: usize,
+    pub cell_size: f32,
+    pub heights: Vec<f32>,
+}
+
+impl Heightfield {
+    // Bilinear sampling needs at least one whole cell, 2x2 heights
+    pub fn new(size: usize, cell_size: f32, height: impl Fn(f32, f32) -> f32) -> Heightfield {
+        assert!(
+            size >= 2,
+            "A heightfield needs at least 2x2 heights, got {}x{}",
+            size,
+            size
+        );
+
+        let mut heights = Vec::with_capacity(size * size);
+        let half = (size - 1) as f32 * cell_size * 0.5;
+
+        for row in 0..size {
+            for column in 0..size {
+                let x = column as f32 * cell_size - half;
+                let z = row as f32 * cell_size - half;
+                heights.push(height(x, z));
+            }
+        }
+
+        Heightfield {
+            size,
+            cell_size,
+            heights,
+        }
+    }
+
+    fn height(&self, column: usize, row: usize) -> f32 {
+        let column = column.min(self.size - 1);
+        let row = row.min(self.size - 1);
+
+        self.heights[row * self.size + column]
+    }
+}
+
+impl Ground for Heightfield {
+    fn height_at(&self, x: f32, z: f32) -> f32 {
+        let half = (self.size - 1) as f32 * self.cell_size * 0.5;
+        let max_cell = (self.size - 1) as f32;
+
+        // Outside the grid the edge heights continue forever
+        let grid_x = ((x + half) / self.cell_size).clamp(0.0, max_cell);
+        let grid_z = ((z + half) / self.cell_size).clamp(0.0, max_cell);
+
+        let column = grid_x.floor() as usize;
+        let row = grid_z.floor() as usize;
+        let tx = grid_x - column as f32;
+        let tz = grid_z - row as f32;
+
+        let top = glm::lerp_scalar(self.height(column, row), self.height(column + 1, row), tx);
+        let bottom = glm::lerp_scalar(
+            self.height(column, row + 1),
+            self.height(column + 1, row + 1),
+            tx,
+        );
+
+        glm::lerp_scalar(top, bottom, tz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn touching_boxes_do_not_intersect() {
+        let floor = Aabb::new(glm::vec3(-1.0, -1.0, -1.0), glm::vec3(1.0, 0.0, 1.0));
+        let on_top = Aabb::new(glm::vec3(-0.5, 0.0, -0.5), glm::vec3(0.5, 1.0, 0.5));
+        let sunk = Aabb::new(glm::vec3(-0.5, -0.1, -0.5), glm::vec3(0.5, 1.0, 0.5));
+
+        assert!(!floor.intersects(&on_top));
+        assert!(floor.intersects(&sunk));
+    }
+
+    #[test]
+    fn heightfield_samples_bilinearly() {
+        // 2x2 heights one unit apart, x goes from -0.5 to 0.5
+        let field = Heightfield::new(2, 1.0, |x, z| if x > 0.0 && z > 0.0 { 1.0 } else { 0.0 });
+
+        assert_eq!(field.height_at(-0.5, -0.5), 0.0);
+        assert_eq!(field.height_at(0.5, 0.5), 1.0);
+        assert!((field.height_at(0.0, 0.0) - 0.25).abs() < 1e-6);
+        // The edge carries on outside the grid
+        assert_eq!(field.height_at(10.0, 10.0), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 2x2")]
+    fn heightfield_needs_two_heights() {
+        Heightfield::new(0, 1.0, |_, _| 0.0);
+    }
+}
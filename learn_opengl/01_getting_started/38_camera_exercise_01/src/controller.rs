@@ -0,0 +1,302 @@
+extern crate nalgebra_glm as glm;
+
+use common::{Camera, CameraMovement};
+
+use crate::collision::{Aabb, Ground};
+
+pub const EYE_HEIGHT: f32 = 1.2;
+pub const BODY_RADIUS: f32 = 0.25;
+pub const HEAD_ROOM: f32 = 0.1;
+pub const GRAVITY: f32 = 9.8;
+pub const JUMP_SPEED: f32 = 4.5;
+pub const STEP_HEIGHT: f32 = 0.2;
+// Stepping up leaves this much between the feet and the box so floating
+// point error doesn't put the feet inside the top and stop the next step
+const SKIN: f32 = 1e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    Fly,
+    Grounded,
+}
+
+// Turns the free flying camera into a "true" fps camera, the eye stays at
+// eye_height above whatever the player is standing on
+pub struct CharacterController {
+    pub mode: CameraMode,
+    pub eye_height: f32,
+    pub body_radius: f32,
+    pub gravity: f32,
+    pub jump_speed: f32,
+    pub vertical_velocity: f32,
+    pub grounded: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CharacterController {
+    pub fn new() -> CharacterController {
+        CharacterController {
+            mode: CameraMode::Grounded,
+            eye_height: EYE_HEIGHT,
+            body_radius: BODY_RADIUS,
+            gravity: GRAVITY,
+            jump_speed: JUMP_SPEED,
+            vertical_velocity: 0.0,
+            grounded: false,
+        }
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Fly => CameraMode::Grounded,
+            CameraMode::Grounded => CameraMode::Fly,
+        };
+
+        self.vertical_velocity = 0.0;
+        self.grounded = false;
+    }
+
+    pub fn jump(&mut self) {
+        if self.mode == CameraMode::Grounded && self.grounded {
+            self.vertical_velocity = self.jump_speed;
+            self.grounded = false;
+        }
+    }
+
+    pub fn process_keyboard(
+        &mut self,
+        camera: &mut Camera,
+        direction: CameraMovement,
+        delta_time: f32,
+        obstacles: &[Aabb],
+    ) {
+        if self.mode == CameraMode::Fly {
+            camera.process_keyboard(direction, delta_time);
+            return;
+        }
+
+        // Looking up or down must not change how fast we walk so only use
+        // the part of the front vector that lies on the ground
+        let flat_front = glm::normalize(&glm::vec3(camera.front.x, 0.0, camera.front.z));
+        let flat_right = glm::normalize(&glm::vec3(camera.right.x, 0.0, camera.right.z));
+        let velocity = camera.movement_speed * delta_time;
+
+        let displacement = match direction {
+            CameraMovement::Forward => flat_front * velocity,
+            CameraMovement::Backward => -flat_front * velocity,
+            CameraMovement::Left => -flat_right * velocity,
+            CameraMovement::Right => flat_right * velocity,
+        };
+
+        // Resolve each axis on its own so we slide along walls instead of sticking
+        self.move_axis(camera, 0, displacement.x, obstacles);
+        self.move_axis(camera, 2, displacement.z, obstacles);
+    }
+
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        delta_time: f32,
+        ground: &dyn Ground,
+        obstacles: &[Aabb],
+    ) {
+        if self.mode == CameraMode::Fly {
+            return;
+        }
+
+        let was_grounded = self.grounded;
+        self.grounded = false;
+
+        self.vertical_velocity -= self.gravity * delta_time;
+        self.move_axis(camera, 1, self.vertical_velocity * delta_time, obstacles);
+
+        // Landed on a box
+        let on_obstacle = self.grounded;
+
+        let ground_height = ground.height_at(camera.position.x, camera.position.z);
+        let feet = camera.position.y - self.eye_height;
+
+        // Stick to the ground when walking down a slope instead of hopping off
+        // it, but not through a box that is being stood on
+        let snap = was_grounded
+            && !on_obstacle
+            && self.vertical_velocity <= 0.0
+            && feet - ground_height < STEP_HEIGHT;
+
+        if feet <= ground_height || snap {
+            camera.position.y = ground_height + self.eye_height;
+            self.vertical_velocity = 0.0;
+            self.grounded = true;
+        }
+    }
+
+    pub fn body(&self, eye: glm::Vec3) -> Aabb {
+        Aabb::new(
+            glm::vec3(
+                eye.x - self.body_radius,
+                eye.y - self.eye_height,
+                eye.z - self.body_radius,
+            ),
+            glm::vec3(
+                eye.x + self.body_radius,
+                eye.y + HEAD_ROOM,
+                eye.z + self.body_radius,
+            ),
+        )
+    }
+
+    fn move_axis(&mut self, camera: &mut Camera, axis: usize, distance: f32, obstacles: &[Aabb]) {
+        if distance == 0.0 {
+            return;
+        }
+
+        camera.position[axis] += distance;
+
+        for obstacle in obstacles {
+            let body = self.body(camera.position);
+
+            if !body.intersects(obstacle) {
+                continue;
+            }
+
+            // Walking into something low enough steps up onto it instead,
+            // as long as there is room up there
+            if axis != 1 && self.grounded {
+                let rise = obstacle.max.y - body.min.y;
+
+                if rise <= STEP_HEIGHT {
+                    let mut lifted = camera.position;
+                    lifted.y = obstacle.max.y + self.eye_height + SKIN;
+                    let lifted_body = self.body(lifted);
+
+                    if !obstacles.iter().any(|other| lifted_body.intersects(other)) {
+                        camera.position = lifted;
+                        continue;
+                    }
+                }
+            }
+
+            // Push the body back out of the box on the side it came from
+            if distance > 0.0 {
+                camera.position[axis] -= body.max[axis] - obstacle.min[axis];
+            } else {
+                camera.position[axis] += obstacle.max[axis] - body.min[axis];
+            }
+
+            if axis == 1 {
+                if distance < 0.0 {
+                    self.grounded = true;
+                }
+
+                self.vertical_velocity = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::GroundPlane;
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    // Looking down -z like the chapter starts
+    fn camera_at(position: glm::Vec3) -> Camera {
+        let mut camera = Camera::new(position, glm::vec3(0.0, 1.0, 0.0), -90.0, 0.0);
+        camera.front = glm::vec3(0.0, 0.0, -1.0);
+        camera.right = glm::vec3(1.0, 0.0, 0.0);
+        camera
+    }
+
+    // A frame of holding W, like the chapter's update
+    fn walk_forward(
+        controller: &mut CharacterController,
+        camera: &mut Camera,
+        frames: usize,
+        obstacles: &[Aabb],
+    ) {
+        let ground = GroundPlane { height: 0.0 };
+
+        for _ in 0..frames {
+            controller.process_keyboard(camera, CameraMovement::Forward, DELTA, obstacles);
+            controller.update(camera, DELTA, &ground, obstacles);
+        }
+    }
+
+    #[test]
+    fn falls_onto_the_ground() {
+        let mut controller = CharacterController::new();
+        let mut camera = camera_at(glm::vec3(0.0, 5.0, 0.0));
+        let ground = GroundPlane { height: 0.0 };
+
+        for _ in 0..120 {
+            controller.update(&mut camera, DELTA, &ground, &[]);
+        }
+
+        assert!(controller.grounded);
+        assert_eq!(camera.position.y, EYE_HEIGHT);
+        assert_eq!(controller.vertical_velocity, 0.0);
+    }
+
+    #[test]
+    fn steps_onto_a_low_box() {
+        let mut controller = CharacterController::new();
+        let mut camera = camera_at(glm::vec3(0.0, EYE_HEIGHT, 0.0));
+        let step = STEP_HEIGHT * 0.75;
+        let obstacles = [Aabb::new(
+            glm::vec3(-1.0, 0.0, -3.0),
+            glm::vec3(1.0, step, -1.0),
+        )];
+
+        walk_forward(&mut controller, &mut camera, 60, &obstacles);
+
+        assert!(camera.position.z < -1.0 - BODY_RADIUS);
+        assert!((camera.position.y - (step + EYE_HEIGHT)).abs() < 1e-3);
+        assert!(controller.grounded);
+    }
+
+    #[test]
+    fn is_stopped_by_a_wall() {
+        let mut controller = CharacterController::new();
+        let mut camera = camera_at(glm::vec3(0.0, EYE_HEIGHT, 0.0));
+        let obstacles = [Aabb::new(
+            glm::vec3(-1.0, 0.0, -3.0),
+            glm::vec3(1.0, 2.0, -1.0),
+        )];
+
+        walk_forward(&mut controller, &mut camera, 60, &obstacles);
+
+        assert!((camera.position.z - (-1.0 + BODY_RADIUS)).abs() < 1e-4);
+        assert_eq!(camera.position.y, EYE_HEIGHT);
+    }
+
+    #[test]
+    fn walks_across_a_box_top() {
+        let mut controller = CharacterController::new();
+        let top = 1.0;
+        let obstacles = [Aabb::new(
+            glm::vec3(-5.0, 0.0, -10.0),
+            glm::vec3(5.0, top, 5.0),
+        )];
+        let mut camera = camera_at(glm::vec3(0.0, top + EYE_HEIGHT + 0.5, 0.0));
+        let ground = GroundPlane { height: 0.0 };
+
+        // Land on it first
+        for _ in 0..60 {
+            controller.update(&mut camera, DELTA, &ground, &obstacles);
+        }
+        assert!(controller.grounded);
+
+        walk_forward(&mut controller, &mut camera, 60, &obstacles);
+
+        // A second of walking covers movement_speed units
+        assert!((camera.position.z + camera.movement_speed).abs() < 0.01);
+        assert!((camera.position.y - (top + EYE_HEIGHT)).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,389 @@
+extern crate nalgebra_glm as glm;
+
+use std::rc::Rc;
+
+use collision::{Aabb, Ground, GroundPlane, Heightfield};
+use common::scene::Transform;
+use common::{
+    App, Assets, Buffer, Camera, CameraMovement, Context, Program, SceneFile, Texture2D,
+    TextureOptions, Usage, Vertex, VertexArray,
+};
+use controller::CharacterController;
+use glfw::{Action, Key, WindowEvent};
+use log::info;
+
+pub mod collision;
+pub mod controller;
+
+const GROUND_SIZE: usize = 41;
+const GROUND_CELL_SIZE: f32 = 0.5;
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct TexturedVertex {
+    position: [f32; 3],
+    tex_coords: [f32; 2],
+}
+
+const fn vertex(position: [f32; 3], tex_coords: [f32; 2]) -> TexturedVertex {
+    TexturedVertex {
+        position,
+        tex_coords,
+    }
+}
+
+struct CameraExercise01 {
+    shader: Program,
+    ground_shader: Program,
+    vao: VertexArray,
+    // Only kept so it lives as long as the vertex array that reads it
+    _vbo: Buffer<TexturedVertex>,
+    ground_vao: VertexArray,
+    ground_vbo: Buffer<TexturedVertex>,
+    texture: Rc<Texture2D>,
+    face_texture: Rc<Texture2D>,
+    ground_texture: Rc<Texture2D>,
+    camera: Camera,
+    // Where the camera was before the last fixed update, render draws from
+    // somewhere between the two
+    previous_position: glm::Vec3,
+    controller: CharacterController,
+    ground_plane: GroundPlane,
+    heightfield: Heightfield,
+    scene: SceneFile,
+    obstacles: Vec<Aabb>,
+    use_heightfield: bool,
+    placed_on_heightfield: bool,
+}
+
+impl App for CameraExercise01 {
+    fn init(ctx: &mut Context) -> Self {
+        ctx.capture_cursor();
+
+        let scene = SceneFile::from_assets(&ctx.assets, "assets/scene.ron");
+
+        // Picks up where the last run left off
+        let start = ctx.saved_camera().unwrap_or_else(|| scene.camera.clone());
+        let camera = Camera::from_start(&start);
+
+        let controller = CharacterController::new();
+
+        let ground_plane = GroundPlane { height: 0.0 };
+        let heightfield = Heightfield::new(GROUND_SIZE, GROUND_CELL_SIZE, |x, z| {
+            // Keep the middle flat and roll some hills up around it
+            let distance = (x * x + z * z).sqrt();
+            let hills = ((x * 0.6).sin() * (z * 0.6).cos() + 1.0) * 0.5;
+
+            hills * glm::smoothstep(3.0, 8.0, distance) * 1.5
+        });
+
+        let shader = Program::from_assets(
+            &mut ctx.assets,
+            "assets/shaders/vertex.vs",
+            "assets/shaders/fragment.fs",
+        )
+        .unwrap();
+        let ground_shader = Program::from_assets(
+            &mut ctx.assets,
+            "assets/shaders/ground.vs",
+            "assets/shaders/ground.fs",
+        )
+        .unwrap();
+
+        #[rustfmt::skip]
+        let vertices = [
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+        ];
+
+        let obstacles = place_cubes(&scene.objects, &heightfield);
+
+        let texture = load_texture(&mut ctx.assets, "assets/textures/container.jpg");
+        let face_texture = load_texture(&mut ctx.assets, "assets/textures/awesomeface.png");
+        let ground_texture = load_texture(&mut ctx.assets, "assets/textures/wall.jpg");
+
+        let vbo = Buffer::vertices(&vertices, Usage::Static);
+        vbo.label("vbo");
+
+        let vao = VertexArray::with_layout(&vbo);
+        vao.label("vao");
+
+        // Uploaded again whenever the ground changes
+        let ground_vbo = Buffer::vertices(&build_ground_mesh(&heightfield), Usage::Dynamic);
+        ground_vbo.label("ground_vbo");
+
+        let ground_vao = VertexArray::with_layout(&ground_vbo);
+        ground_vao.label("ground_vao");
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+
+            shader.use_program();
+            shader.set_int("texture1", 0);
+            shader.set_int("texture2", 1);
+
+            ground_shader.use_program();
+            ground_shader.set_int("texture1", 0);
+        }
+
+        CameraExercise01 {
+            shader,
+            ground_shader,
+            vao,
+            _vbo: vbo,
+            ground_vao,
+            ground_vbo,
+            texture,
+            face_texture,
+            ground_texture,
+            previous_position: camera.position,
+            camera,
+            controller,
+            ground_plane,
+            heightfield,
+            scene,
+            obstacles,
+            use_heightfield: true,
+            placed_on_heightfield: true,
+        }
+    }
+
+    // Walking, jumping and falling run at a fixed rate so a jump always goes
+    // as high no matter the frame rate
+    fn fixed_update(&mut self, ctx: &mut Context) {
+        self.previous_position = self.camera.position;
+
+        let movements = [
+            (Key::W, CameraMovement::Forward),
+            (Key::S, CameraMovement::Backward),
+            (Key::A, CameraMovement::Left),
+            (Key::D, CameraMovement::Right),
+        ];
+
+        for (key, movement) in movements {
+            if ctx.input.key_down(key) {
+                self.controller.process_keyboard(
+                    &mut self.camera,
+                    movement,
+                    ctx.time.fixed_delta,
+                    &self.obstacles,
+                );
+            }
+        }
+
+        if ctx.input.key_down(Key::Space) {
+            self.controller.jump();
+        }
+
+        let ground: &dyn Ground = if self.use_heightfield {
+            &self.heightfield
+        } else {
+            &self.ground_plane
+        };
+        self.controller.update(
+            &mut self.camera,
+            ctx.time.fixed_delta,
+            ground,
+            &self.obstacles,
+        );
+    }
+
+    // Looking around is polled every frame so the mouse stays responsive
+    fn update(&mut self, ctx: &mut Context) {
+        let scene_changed = self.scene.reload_if_changed();
+
+        if scene_changed || self.use_heightfield != self.placed_on_heightfield {
+            self.rebuild_ground();
+        }
+
+        let (x_offset, y_offset) = ctx.input.mouse_offset();
+        if x_offset != 0.0 || y_offset != 0.0 {
+            self.camera.process_mouse_movement(x_offset, y_offset, true);
+        }
+
+        let scroll_offset = ctx.input.scroll_offset();
+        if scroll_offset != 0.0 {
+            self.camera.process_mouse_scroll(scroll_offset);
+        }
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        unsafe {
+            let [red, green, blue, alpha] = self.scene.clear_color;
+            gl::ClearColor(red, green, blue, alpha);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // Note the glm rust port has these paramaters wrong lol
+            let projection = glm::perspective(
+                ctx.aspect_ratio(),
+                self.camera.zoom.to_radians(),
+                0.1,
+                100.0,
+            );
+
+            let eye = glm::lerp(
+                &self.previous_position,
+                &self.camera.position,
+                ctx.time.alpha,
+            );
+            let view = glm::look_at(&eye, &(eye + self.camera.front), &self.camera.up);
+
+            self.ground_shader.use_program();
+            self.ground_shader.set_mat4("projection", &projection);
+            self.ground_shader.set_mat4("view", &view);
+            self.ground_shader.set_mat4("model", &glm::Mat4::identity());
+
+            self.ground_texture.bind(0);
+
+            self.ground_vao
+                .draw_arrays(gl::TRIANGLES, 0, self.ground_vbo.len());
+
+            self.shader.use_program();
+            self.shader.set_mat4("projection", &projection);
+            self.shader.set_mat4("view", &view);
+
+            self.texture.bind(0);
+            self.face_texture.bind(1);
+
+            for obstacle in self.obstacles.iter() {
+                let model = glm::translate(&glm::Mat4::identity(), &obstacle.center());
+
+                self.shader.set_mat4("model", &model);
+
+                self.vao.draw_arrays(gl::TRIANGLES, 0, 36);
+            }
+
+            gl::BindVertexArray(0);
+        }
+    }
+
+    fn event(&mut self, _ctx: &mut Context, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(Key::C, _, Action::Press, _) => {
+                self.controller.toggle_mode();
+                info!("Camera mode = {:?}", self.controller.mode);
+            }
+            WindowEvent::Key(Key::G, _, Action::Press, _) => {
+                self.use_heightfield = !self.use_heightfield;
+            }
+            _ => {}
+        }
+    }
+
+    fn shutdown(&mut self, ctx: &mut Context) {
+        ctx.save_camera(self.camera.start());
+    }
+}
+
+impl CameraExercise01 {
+    // Puts the cubes and the ground mesh on whichever ground is active
+    fn rebuild_ground(&mut self) {
+        let ground: &dyn Ground = if self.use_heightfield {
+            &self.heightfield
+        } else {
+            &self.ground_plane
+        };
+
+        self.obstacles = place_cubes(&self.scene.objects, ground);
+        self.ground_vbo.set_data(&build_ground_mesh(ground));
+        self.placed_on_heightfield = self.use_heightfield;
+    }
+}
+
+fn main() {
+    #[cfg(feature = "embedded-assets")]
+    common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
+
+    common::run::<CameraExercise01>("LearnOpenGL");
+}
+
+// Sits every cube on top of the ground, or on top of the cube below it. The
+// x and z of each object are on the ground, y is how many cubes are
+// stacked underneath.
+fn place_cubes(objects: &[Transform], ground: &dyn Ground) -> Vec<Aabb> {
+    objects
+        .iter()
+        .map(|object| {
+            let position = object.position();
+            let base = ground.height_at(position.x, position.z);
+            let center = glm::vec3(position.x, base + position.y + 0.5, position.z);
+
+            Aabb::from_center(center, glm::vec3(0.5, 0.5, 0.5))
+        })
+        .collect()
+}
+
+// Two triangles per grid cell with a tiling texture coordinate
+fn build_ground_mesh(ground: &dyn Ground) -> Vec<TexturedVertex> {
+    let cells = GROUND_SIZE - 1;
+    let half = cells as f32 * GROUND_CELL_SIZE * 0.5;
+    let mut vertices = Vec::with_capacity(cells * cells * 6);
+
+    for row in 0..cells {
+        for column in 0..cells {
+            let x0 = column as f32 * GROUND_CELL_SIZE - half;
+            let z0 = row as f32 * GROUND_CELL_SIZE - half;
+            let x1 = x0 + GROUND_CELL_SIZE;
+            let z1 = z0 + GROUND_CELL_SIZE;
+
+            for (x, z) in [(x0, z0), (x0, z1), (x1, z1), (x1, z1), (x1, z0), (x0, z0)] {
+                vertices.push(vertex([x, ground.height_at(x, z), z], [x * 0.5, z * 0.5]));
+            }
+        }
+    }
+
+    vertices
+}
+
+// Flipped like the tutorial's stbi_set_flip_vertically_on_load, the format
+// comes from the image so the face keeps its alpha
+fn load_texture(assets: &mut Assets, path: &str) -> Rc<Texture2D> {
+    assets
+        .texture(
+            path,
+            &TextureOptions {
+                flip: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+}
@@ -0,0 +1,254 @@
+extern crate nalgebra_glm as glm;
+
+use std::rc::Rc;
+
+use common::cubemap::FACES;
+use common::{
+    App, Buffer, Camera, CameraMovement, Context, Cubemap, Program, Sampler, Skybox, Texture2D,
+    TextureOptions, Usage, Vertex, VertexArray,
+};
+use glfw::{Action, Key, WindowEvent};
+use log::info;
+
+#[derive(Clone, Copy, Vertex)]
+#[repr(C)]
+struct TexturedVertex {
+    position: [f32; 3],
+    tex_coords: [f32; 2],
+}
+
+const fn vertex(position: [f32; 3], tex_coords: [f32; 2]) -> TexturedVertex {
+    TexturedVertex {
+        position,
+        tex_coords,
+    }
+}
+
+struct Cubemaps {
+    shader: Program,
+    cube_vao: VertexArray,
+    // Only kept so it lives as long as the vertex array that reads it
+    _cube_vbo: Buffer<TexturedVertex>,
+    cube_texture: Rc<Texture2D>,
+    skybox: Skybox,
+    // The same sky twice, from six face images and from one panorama turned
+    // into a cube map on the GPU. E switches between them.
+    faces: Cubemap,
+    panorama: Cubemap,
+    show_panorama: bool,
+    camera: Camera,
+}
+
+impl App for Cubemaps {
+    fn init(ctx: &mut Context) -> Self {
+        ctx.capture_cursor();
+
+        // Picks up where the last run left off
+        let start = ctx.saved_camera().unwrap_or_default();
+        let camera = Camera::from_start(&start);
+
+        let shader = Program::from_assets(
+            &mut ctx.assets,
+            "assets/shaders/cubemaps.vs",
+            "assets/shaders/cubemaps.fs",
+        )
+        .unwrap();
+
+        #[rustfmt::skip]
+        let cube_vertices = [
+            //     positions           texture coords
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 0.0]),
+
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 1.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5, -0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5, -0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5, -0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5, -0.5, -0.5], [0.0, 1.0]),
+
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+            vertex([ 0.5,  0.5, -0.5], [1.0, 1.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([ 0.5,  0.5,  0.5], [1.0, 0.0]),
+            vertex([-0.5,  0.5,  0.5], [0.0, 0.0]),
+            vertex([-0.5,  0.5, -0.5], [0.0, 1.0]),
+        ];
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let cube_vbo = Buffer::vertices(&cube_vertices, Usage::Static);
+        cube_vbo.label("cube_vbo");
+
+        let cube_vao = VertexArray::with_layout(&cube_vbo);
+        cube_vao.label("cube_vao");
+
+        let cube_texture = ctx
+            .assets
+            .texture(
+                "assets/textures/container.jpg",
+                &TextureOptions {
+                    flip: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // right, left, top, bottom, front, back like the tutorial's list
+        let faces = Cubemap::from_assets(
+            &ctx.assets,
+            FACES.map(|face| format!("assets/textures/skybox/{}.png", face)),
+            &Default::default(),
+        )
+        .unwrap();
+        faces.label("skybox_faces");
+
+        // Only needed until it has been drawn into the faces
+        let sky = ctx
+            .assets
+            .texture(
+                "assets/textures/sky.png",
+                &TextureOptions {
+                    flip: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let panorama = Cubemap::from_equirectangular(&sky, 256, &Sampler::default()).unwrap();
+        panorama.label("skybox_panorama");
+
+        shader.use_program();
+        shader.set_int("texture1", 0);
+
+        Cubemaps {
+            shader,
+            cube_vao,
+            _cube_vbo: cube_vbo,
+            cube_texture,
+            skybox: Skybox::new(),
+            faces,
+            panorama,
+            show_panorama: false,
+            camera,
+        }
+    }
+
+    // This would be the process input function in the C++ version
+    fn update(&mut self, ctx: &mut Context) {
+        let delta_time = ctx.time.delta;
+
+        if ctx.input.key_down(Key::W) {
+            self.camera
+                .process_keyboard(CameraMovement::Forward, delta_time);
+        }
+
+        if ctx.input.key_down(Key::S) {
+            self.camera
+                .process_keyboard(CameraMovement::Backward, delta_time);
+        }
+
+        if ctx.input.key_down(Key::A) {
+            self.camera
+                .process_keyboard(CameraMovement::Left, delta_time);
+        }
+
+        if ctx.input.key_down(Key::D) {
+            self.camera
+                .process_keyboard(CameraMovement::Right, delta_time);
+        }
+
+        let (x_offset, y_offset) = ctx.input.mouse_offset();
+        if x_offset != 0.0 || y_offset != 0.0 {
+            self.camera.process_mouse_movement(x_offset, y_offset, true);
+        }
+
+        let scroll_offset = ctx.input.scroll_offset();
+        if scroll_offset != 0.0 {
+            self.camera.process_mouse_scroll(scroll_offset);
+        }
+    }
+
+    fn render(&mut self, ctx: &mut Context) {
+        unsafe {
+            gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+
+        // Note the glm rust port has these paramaters wrong lol
+        let projection = glm::perspective(
+            ctx.aspect_ratio(),
+            self.camera.zoom.to_radians(),
+            0.1,
+            100.0,
+        );
+        let view = self.camera.view_matrix();
+
+        self.shader.use_program();
+        self.shader.set_mat4("model", &glm::Mat4::identity());
+        self.shader.set_mat4("view", &view);
+        self.shader.set_mat4("projection", &projection);
+
+        self.cube_texture.bind(0);
+        self.cube_vao.draw_arrays(gl::TRIANGLES, 0, 36);
+
+        // Last, so the sky is only drawn where the cube isn't
+        let sky = if self.show_panorama {
+            &self.panorama
+        } else {
+            &self.faces
+        };
+        self.skybox.draw(sky, &view, &projection);
+    }
+
+    fn event(&mut self, _ctx: &mut Context, event: &WindowEvent) {
+        if let WindowEvent::Key(Key::E, _, Action::Press, _) = *event {
+            self.show_panorama = !self.show_panorama;
+            info!(
+                "Skybox = {}",
+                if self.show_panorama {
+                    "panorama"
+                } else {
+                    "faces"
+                }
+            );
+        }
+    }
+
+    fn shutdown(&mut self, ctx: &mut Context) {
+        ctx.save_camera(self.camera.start());
+    }
+}
+
+fn main() {
+    #[cfg(feature = "embedded-assets")]
+    common::assets::embed(include!(concat!(env!("OUT_DIR"), "/assets.rs")));
+
+    common::run::<Cubemaps>("LearnOpenGL");
+}